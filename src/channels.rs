@@ -11,6 +11,7 @@ use crate::game::team::{Team, TeamId};
 
 const TEAM_CHANNELS_CATEGORY: &'static str = "Team Channels";
 const QUIZMASTER_ROLE: &'static str = "quizmaster";
+const QUIZMASTER_CHANNEL: &str = "quizmaster";
 
 pub fn update_team_channels(
     ctx: &SerenityContext,
@@ -58,6 +59,11 @@ pub fn update_team_channels(
     // Create missing team channels
     let mut channel_ids = HashMap::new();
     for team in teams {
+        // Solo players get their messages in private instead of a team channel
+        if let TeamId::Player(user_id) = &team.id {
+            channel_ids.insert(team.id.clone(), user_id.create_dm_channel(&ctx.http)?.id);
+            continue;
+        }
         let channel_id = match channels.iter().find(|(_id, channel)| {
             channel.name() == team.get_display_name()
                 && channel.category_id == Some(team_channel_category)
@@ -134,6 +140,36 @@ pub fn update_team_channels(
     Ok(channel_ids)
 }
 
+// Private channel where the bot reports problems that only the quizmaster can act on
+pub fn get_quizmaster_channel(ctx: &SerenityContext, guild_id: GuildId) -> Result<ChannelId> {
+    let channels = guild_id.channels(&ctx.http)?;
+    let existing_channel = channels.iter().find(|(_id, channel)| {
+        channel.kind == ChannelType::Text && channel.name() == QUIZMASTER_CHANNEL
+    });
+    if let Some((channel_id, _channel)) = existing_channel {
+        return Ok(*channel_id);
+    }
+
+    let everyone_role_id = RoleId::from(*guild_id.as_u64());
+    let quizmaster_role_id = get_quizmaster_role_id(ctx, guild_id)?;
+    println!("Creating quizmaster channel: {}", QUIZMASTER_CHANNEL);
+    let channel = guild_id.create_channel(&ctx.http, |c| {
+        c.name(QUIZMASTER_CHANNEL).permissions(vec![
+            PermissionOverwrite {
+                deny: Permissions::READ_MESSAGES,
+                allow: Permissions::empty(),
+                kind: PermissionOverwriteType::Role(everyone_role_id),
+            },
+            PermissionOverwrite {
+                deny: Permissions::empty(),
+                allow: Permissions::READ_MESSAGES,
+                kind: PermissionOverwriteType::Role(quizmaster_role_id),
+            },
+        ])
+    })?;
+    Ok(channel.id)
+}
+
 pub fn get_quizmaster_role_id(ctx: &SerenityContext, guild_id: GuildId) -> Result<RoleId> {
     let guild = guild_id.to_partial_guild(&ctx.http)?;
     match guild.role_by_name(QUIZMASTER_ROLE) {
@@ -4,37 +4,71 @@ use serenity::{
     framework::standard::macros::{command, group},
     framework::standard::{Args, CommandError, CommandResult},
     model::channel::Message,
+    model::id::GuildId,
+    model::misc::Mentionable,
+    model::user::User,
 };
 
 use crate::channels::*;
 use crate::commands::*;
-use crate::game::pool::Pool as GamePool;
+use crate::game::pool::{GameHandle, Pool as GamePool};
+use crate::game::stats::{describe_leaderboard, describe_player, PlayerStats};
+use crate::game::{Game, GameMode};
+use crate::output::discord::DiscordGameOutput;
+use crate::output::recorder::Recorder;
+
+const LEADERBOARD_SIZE: usize = 10;
 
 #[group]
-#[commands(guess, team, wager)]
+#[commands(guess, leaderboard, stats, team, wager)]
 struct Main;
 
+// Private messages are routed to the solo game the player takes part in
+fn find_game(ctx: &SerenityContext, msg: &Message) -> Result<(GuildId, GameHandle)> {
+    let game_pool = ctx
+        .data
+        .read()
+        .get::<GamePool>()
+        .cloned()
+        .expect("Expected GamePool in ShareMap.");
+    match msg.guild_id {
+        Some(guild_id) => Ok((guild_id, game_pool.get_game(ctx, guild_id))),
+        None => game_pool
+            .find_solo_game(msg.author.id)
+            .context("Join a solo quiz from a server channel before playing in private"),
+    }
+}
+
+// Solo players join on their first guess or wager, and need a private channel before the game talks to them
+fn join_solo_game(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    game: &mut Game<Recorder<DiscordGameOutput>>,
+    player: &User,
+) -> Result<()> {
+    if game.get_mode() == GameMode::Solo && game.add_solo_player(player.id, &player.name)? {
+        let channel_ids = update_team_channels(ctx, guild_id, &game.get_teams())?;
+        game.update_team_channels(channel_ids);
+    }
+    Ok(())
+}
+
 #[command]
 fn guess(ctx: &mut SerenityContext, msg: &Message, args: Args) -> CommandResult {
     let result = || -> Result<()> {
-        let guild_id = ctx
-            .cache
-            .read()
-            .guild_channel(msg.channel_id)
-            .context("Server not found")?
-            .read()
-            .guild_id;
-        let game_pool = ctx
-            .data
-            .read()
-            .get::<GamePool>()
-            .cloned()
-            .expect("Expected GamePool in ShareMap.");
-        let game_lock = game_pool.get_game(ctx, guild_id);
+        let (guild_id, game_lock) = find_game(ctx, msg)?;
         let mut game = game_lock.lock();
 
+        // Keep solo guesses made in a shared channel from helping the other players
+        if game.get_mode() == GameMode::Solo && msg.guild_id.is_some() {
+            if let Err(e) = msg.delete(&ctx) {
+                eprintln!("Could not hide guess: {:?}", e);
+            }
+        }
+
         let guess = args.rest();
         if guess.trim().len() != 0 {
+            join_solo_game(ctx, guild_id, &mut game, &msg.author)?;
             game.guess(msg.author.id, &guess)?;
         }
         Ok(())
@@ -93,6 +127,26 @@ fn team(ctx: &mut SerenityContext, msg: &Message, args: Args) -> CommandResult {
 #[command]
 fn wager(ctx: &mut SerenityContext, msg: &Message, mut args: Args) -> CommandResult {
     let result = || -> Result<()> {
+        let (guild_id, game_lock) = find_game(ctx, msg)?;
+        let mut game = game_lock.lock();
+
+        let amount = args.single()?;
+        join_solo_game(ctx, guild_id, &mut game, &msg.author)?;
+        game.wager(msg.author.id, amount)?;
+        Ok(())
+    }();
+
+    if let Err(e) = result {
+        eprintln!("{:#}", e);
+        check_msg(msg.reply(&ctx.http, format!("{}", e)));
+        return Err(CommandError(e.to_string()));
+    }
+    Ok(())
+}
+
+#[command]
+fn stats(ctx: &mut SerenityContext, msg: &Message) -> CommandResult {
+    let result = || -> Result<String> {
         let guild_id = ctx
             .cache
             .read()
@@ -107,17 +161,62 @@ fn wager(ctx: &mut SerenityContext, msg: &Message, mut args: Args) -> CommandRes
             .cloned()
             .expect("Expected GamePool in ShareMap.");
         let game_lock = game_pool.get_game(ctx, guild_id);
-        let mut game = game_lock.lock();
+        let game = game_lock.lock();
 
-        let amount = args.single()?;
-        game.wager(msg.author.id, amount)?;
-        Ok(())
+        let user = msg.mentions.first().unwrap_or(&msg.author);
+        let stats = game.get_player_stats(user.id);
+        Ok(describe_player(&user.mention(), &stats))
     }();
 
-    if let Err(e) = result {
-        eprintln!("{:#}", e);
-        check_msg(msg.reply(&ctx.http, format!("{}", e)));
-        return Err(CommandError(e.to_string()));
+    match result {
+        Ok(description) => {
+            check_msg(msg.reply(&ctx.http, description));
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("{:#}", e);
+            check_msg(msg.reply(&ctx.http, format!("{}", e)));
+            Err(CommandError(e.to_string()))
+        }
+    }
+}
+
+#[command]
+fn leaderboard(ctx: &mut SerenityContext, msg: &Message) -> CommandResult {
+    let result = || -> Result<String> {
+        let guild_id = ctx
+            .cache
+            .read()
+            .guild_channel(msg.channel_id)
+            .context("Server not found")?
+            .read()
+            .guild_id;
+        let game_pool = ctx
+            .data
+            .read()
+            .get::<GamePool>()
+            .cloned()
+            .expect("Expected GamePool in ShareMap.");
+        let game_lock = game_pool.get_game(ctx, guild_id);
+        let game = game_lock.lock();
+
+        let entries: Vec<(String, PlayerStats)> = game
+            .get_leaderboard(LEADERBOARD_SIZE)
+            .into_iter()
+            .map(|(user_id, stats)| (user_id.mention(), stats))
+            .collect();
+        Ok(describe_leaderboard(&entries))
+    }();
+
+    match result {
+        Ok(description) => {
+            check_msg(msg.reply(&ctx.http, description));
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("{:#}", e);
+            check_msg(msg.reply(&ctx.http, format!("{}", e)));
+            Err(CommandError(e.to_string()))
+        }
     }
-    Ok(())
 }
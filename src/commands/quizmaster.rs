@@ -10,7 +10,12 @@ use serenity::{
 use crate::channels::*;
 use crate::commands::*;
 use crate::game::pool::Pool as GamePool;
-use crate::game::team::TeamId;
+use crate::game::quiz::definition::QuizDefinition;
+use crate::game::quiz::settings::SettingsOverrides;
+use crate::game::team::{describe_score_history, sanitize_name, TeamId};
+use crate::game::GameMode;
+use crate::preload::cache;
+use crate::preload::Song;
 use crate::VoiceManager;
 
 const ERROR_USER_NOT_IN_VOICE: &'static str = "You must be in a voice channel to use this command.";
@@ -19,7 +24,23 @@ const ERROR_BOT_NOT_IN_VOICE: &'static str =
 
 #[group]
 #[allowed_roles("quizmaster")]
-#[commands(begin, disband, end, join, pause, score, skip, unpause)]
+#[commands(
+    accept,
+    begin,
+    continue_quiz,
+    disband,
+    end,
+    history,
+    join,
+    mode,
+    pause,
+    reject,
+    resume,
+    score,
+    skip,
+    undo,
+    unpause
+)]
 struct Main;
 
 #[group]
@@ -28,6 +49,66 @@ struct Main;
 #[commands(scores, teams)]
 struct Reset;
 
+#[group]
+#[allowed_roles("quizmaster")]
+#[prefix = "cache"]
+#[commands(list, prune, warm)]
+struct Cache;
+
+const MAX_CACHE_ENTRIES_LISTED: usize = 20;
+
+// Solo players are designated by mentioning them, teams by their name
+fn parse_team_id(msg: &Message, args: &Args) -> Result<TeamId> {
+    match msg.mentions.first() {
+        Some(user) => Ok(TeamId::Player(user.id)),
+        None => Ok(TeamId::TeamName(sanitize_name(args.rest())?)),
+    }
+}
+
+fn rejudge_guess(
+    ctx: &mut SerenityContext,
+    msg: &Message,
+    args: Args,
+    is_correct: bool,
+) -> CommandResult {
+    let result = || -> Result<()> {
+        let guild_id = msg
+            .guild(&ctx.cache)
+            .context(ERROR_MISSING_GUILD)?
+            .read()
+            .id;
+        let game_pool = ctx
+            .data
+            .read()
+            .get::<GamePool>()
+            .cloned()
+            .expect("Expected GamePool in ShareMap.");
+        let game_lock = game_pool.get_game(ctx, guild_id);
+        let mut game = game_lock.lock();
+
+        let team_id = parse_team_id(msg, &args)?;
+        game.rejudge_guess(team_id, is_correct)?;
+        Ok(())
+    }();
+
+    if let Err(e) = result {
+        eprintln!("{:#}", e);
+        check_msg(msg.reply(&ctx.http, format!("{}", e)));
+        return Err(CommandError(e.to_string()));
+    }
+    Ok(())
+}
+
+#[command]
+fn accept(ctx: &mut SerenityContext, msg: &Message, args: Args) -> CommandResult {
+    rejudge_guess(ctx, msg, args, true)
+}
+
+#[command]
+fn reject(ctx: &mut SerenityContext, msg: &Message, args: Args) -> CommandResult {
+    rejudge_guess(ctx, msg, args, false)
+}
+
 #[command]
 fn begin(ctx: &mut SerenityContext, msg: &Message, args: Args) -> CommandResult {
     let result = || -> Result<()> {
@@ -56,12 +137,16 @@ fn begin(ctx: &mut SerenityContext, msg: &Message, args: Args) -> CommandResult
         let game_lock = game_pool.get_game(ctx, guild_id);
         let mut game = game_lock.lock();
 
-        let path_string = args.rest();
+        let (path_string, settings_overrides) = SettingsOverrides::split_from_path(args.rest())?;
         if path_string.is_empty() {
             return Err(anyhow!("Filename cannot be blank"));
         }
+        // The quizmaster channel is only needed once a quiz reports on its preload
+        let quizmaster_channel = get_quizmaster_channel(ctx, guild_id)
+            .context("Could not initialize quizmaster channel")?;
+        game.update_quizmaster_channel(quizmaster_channel);
         let path = Path::new(&path_string);
-        game.begin(path)
+        game.begin(path, settings_overrides)
             .with_context(|| format!("Could not begin quiz with path {:?}", path))?;
         Ok(())
     }();
@@ -146,6 +231,45 @@ fn end(ctx: &mut SerenityContext, msg: &Message) -> CommandResult {
     Ok(())
 }
 
+#[command]
+fn history(ctx: &mut SerenityContext, msg: &Message, args: Args) -> CommandResult {
+    let result = || -> Result<String> {
+        let guild_id = msg
+            .guild(&ctx.cache)
+            .context(ERROR_MISSING_GUILD)?
+            .read()
+            .id;
+        let game_pool = ctx
+            .data
+            .read()
+            .get::<GamePool>()
+            .cloned()
+            .expect("Expected GamePool in ShareMap.");
+        let game_lock = game_pool.get_game(ctx, guild_id);
+        let game = game_lock.lock();
+
+        let team_id = parse_team_id(msg, &args)?;
+        let history = game.get_score_history(&team_id)?;
+        let name = match &team_id {
+            TeamId::TeamName(name) => name.clone(),
+            TeamId::Player(user_id) => user_id.mention(),
+        };
+        Ok(describe_score_history(&name, &history))
+    }();
+
+    match result {
+        Ok(description) => {
+            check_msg(msg.reply(&ctx.http, description));
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("{:#}", e);
+            check_msg(msg.reply(&ctx.http, format!("{}", e)));
+            Err(CommandError(e.to_string()))
+        }
+    }
+}
+
 #[command]
 fn join(ctx: &mut SerenityContext, msg: &Message) -> CommandResult {
     let guild = msg.guild(&ctx.cache).context(ERROR_MISSING_GUILD)?;
@@ -189,6 +313,52 @@ fn join(ctx: &mut SerenityContext, msg: &Message) -> CommandResult {
     Ok(())
 }
 
+#[command]
+fn mode(ctx: &mut SerenityContext, msg: &Message, args: Args) -> CommandResult {
+    let result = || -> Result<GameMode> {
+        let guild_id = msg
+            .guild(&ctx.cache)
+            .context(ERROR_MISSING_GUILD)?
+            .read()
+            .id;
+        let game_pool = ctx
+            .data
+            .read()
+            .get::<GamePool>()
+            .cloned()
+            .expect("Expected GamePool in ShareMap.");
+        let game_lock = game_pool.get_game(ctx, guild_id);
+        let mut game = game_lock.lock();
+
+        let mode = args.rest().trim().parse()?;
+        game.set_mode(mode)?;
+
+        let channel_ids = update_team_channels(ctx, guild_id, &game.get_teams())?;
+        game.update_team_channels(channel_ids);
+
+        Ok(mode)
+    }();
+
+    match result {
+        Ok(GameMode::Teams) => {
+            check_msg(msg.reply(&ctx.http, "Players now compete in teams."));
+            Ok(())
+        }
+        Ok(GameMode::Solo) => {
+            check_msg(msg.reply(
+                &ctx.http,
+                "Players now compete on their own. They can `!guess` right away, in private or here.",
+            ));
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("{:#}", e);
+            check_msg(msg.reply(&ctx.http, format!("{}", e)));
+            Err(CommandError(e.to_string()))
+        }
+    }
+}
+
 #[command]
 fn pause(ctx: &mut SerenityContext, msg: &Message) -> CommandResult {
     let guild_id = ctx
@@ -219,6 +389,47 @@ fn pause(ctx: &mut SerenityContext, msg: &Message) -> CommandResult {
     Ok(())
 }
 
+#[command]
+fn resume(ctx: &mut SerenityContext, msg: &Message) -> CommandResult {
+    let result = || -> Result<()> {
+        let guild_id = msg
+            .guild(&ctx.cache)
+            .context(ERROR_MISSING_GUILD)?
+            .read()
+            .id;
+        let voice_manager_lock = ctx
+            .data
+            .read()
+            .get::<VoiceManager>()
+            .cloned()
+            .expect("Expected VoiceManager in ShareMap.");
+        let voice_manager = voice_manager_lock.lock();
+        if voice_manager.get(guild_id).is_none() {
+            return Err(anyhow!(ERROR_BOT_NOT_IN_VOICE));
+        }
+
+        let game_pool = ctx
+            .data
+            .read()
+            .get::<GamePool>()
+            .cloned()
+            .expect("Expected GamePool in ShareMap.");
+        let game_lock = game_pool.get_game(ctx, guild_id);
+        let mut game = game_lock.lock();
+
+        game.resume(|teams| update_team_channels(ctx, guild_id, teams))
+            .context("Could not resume quiz")?;
+        Ok(())
+    }();
+
+    if let Err(e) = result {
+        eprintln!("{:#}", e);
+        check_msg(msg.reply(&ctx.http, format!("{}", e)));
+        return Err(CommandError(e.to_string()));
+    }
+    Ok(())
+}
+
 #[command]
 fn score(ctx: &mut SerenityContext, msg: &Message, mut args: Args) -> CommandResult {
     let result = || -> Result<()> {
@@ -258,6 +469,36 @@ fn score(ctx: &mut SerenityContext, msg: &Message, mut args: Args) -> CommandRes
     Ok(())
 }
 
+#[command("continue")]
+fn continue_quiz(ctx: &mut SerenityContext, msg: &Message) -> CommandResult {
+    let result = || -> Result<()> {
+        let guild_id = ctx
+            .cache
+            .read()
+            .guild_channel(msg.channel_id)
+            .context("Server not found")?
+            .read()
+            .guild_id;
+        let game_pool = ctx
+            .data
+            .read()
+            .get::<GamePool>()
+            .cloned()
+            .expect("Expected GamePool in ShareMap.");
+        let game_lock = game_pool.get_game(ctx, guild_id);
+        let mut game = game_lock.lock();
+        game.continue_quiz()?;
+        Ok(())
+    }();
+
+    if let Err(e) = result {
+        eprintln!("{:#}", e);
+        check_msg(msg.reply(&ctx.http, format!("{}", e)));
+        return Err(CommandError(e.to_string()));
+    }
+    Ok(())
+}
+
 #[command]
 fn skip(ctx: &mut SerenityContext, msg: &Message) -> CommandResult {
     let result = || -> Result<()> {
@@ -358,6 +599,34 @@ fn teams(ctx: &mut SerenityContext, msg: &Message) -> CommandResult {
     Ok(())
 }
 
+#[command]
+fn undo(ctx: &mut SerenityContext, msg: &Message) -> CommandResult {
+    let result = || -> Result<()> {
+        let guild_id = msg
+            .guild(&ctx.cache)
+            .context(ERROR_MISSING_GUILD)?
+            .read()
+            .id;
+        let game_pool = ctx
+            .data
+            .read()
+            .get::<GamePool>()
+            .cloned()
+            .expect("Expected GamePool in ShareMap.");
+        let game_lock = game_pool.get_game(ctx, guild_id);
+        let mut game = game_lock.lock();
+        game.undo()?;
+        Ok(())
+    }();
+
+    if let Err(e) = result {
+        eprintln!("{:#}", e);
+        check_msg(msg.reply(&ctx.http, format!("{}", e)));
+        return Err(CommandError(e.to_string()));
+    }
+    Ok(())
+}
+
 #[command]
 fn unpause(ctx: &mut SerenityContext, msg: &Message) -> CommandResult {
     let result = || -> Result<()> {
@@ -387,3 +656,70 @@ fn unpause(ctx: &mut SerenityContext, msg: &Message) -> CommandResult {
     }
     Ok(())
 }
+
+#[command]
+fn list(ctx: &mut SerenityContext, msg: &Message) -> CommandResult {
+    let description = cache::describe(&cache::list(), MAX_CACHE_ENTRIES_LISTED);
+    check_msg(msg.reply(&ctx.http, description));
+    Ok(())
+}
+
+#[command]
+fn prune(ctx: &mut SerenityContext, msg: &Message) -> CommandResult {
+    let result = || -> Result<String> {
+        let evicted = cache::prune()?;
+        Ok(cache::describe_pruned(&evicted))
+    }();
+
+    match result {
+        Ok(description) => {
+            check_msg(msg.reply(&ctx.http, description));
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("{:#}", e);
+            check_msg(msg.reply(&ctx.http, format!("{}", e)));
+            Err(CommandError(e.to_string()))
+        }
+    }
+}
+
+#[command]
+fn warm(ctx: &mut SerenityContext, msg: &Message, args: Args) -> CommandResult {
+    let result = || -> Result<String> {
+        let path_string = args.rest();
+        if path_string.is_empty() {
+            return Err(anyhow!("Filename cannot be blank"));
+        }
+        let path = Path::new(&path_string);
+        let definition = QuizDefinition::open(path)
+            .with_context(|| format!("Could not open quiz with path {:?}", path))?;
+        let songs: Vec<Song> = definition
+            .get_questions()
+            .iter()
+            .map(|q| q.get_song())
+            .collect();
+        let description = format!(
+            "Preloading {} song(s), I will reply once they are ready",
+            songs.len()
+        );
+        let http = ctx.http.clone();
+        let msg = msg.clone();
+        cache::warm(songs, move |warmed| {
+            check_msg(msg.reply(&http, warmed));
+        })?;
+        Ok(description)
+    }();
+
+    match result {
+        Ok(description) => {
+            check_msg(msg.reply(&ctx.http, description));
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("{:#}", e);
+            check_msg(msg.reply(&ctx.http, format!("{}", e)));
+            Err(CommandError(e.to_string()))
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use anyhow::*;
+use directories_next::BaseDirs;
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::preload::cache::EvictionPolicy;
+use crate::preload::download::DownloaderConfig;
+use crate::preload::normalize::NormalizationConfig;
+
+const CONFIG_PATH_VARIABLE: &str = "LEVEL99_CONFIG";
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub offline: bool,
+    pub downloader: DownloaderConfig,
+    pub cache: EvictionPolicy,
+    pub normalization: NormalizationConfig,
+}
+
+impl Config {
+    pub fn from_toml(content: &str) -> Result<Config> {
+        toml::from_str(content).context("Could not parse configuration")
+    }
+
+    // Missing configuration files are not an error, every setting has a default value
+    pub fn load() -> Result<Config> {
+        let path = get_config_path()?;
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        Config::from_toml(&content)
+            .with_context(|| format!("Invalid configuration file {:?}", path))
+    }
+}
+
+fn get_config_path() -> Result<PathBuf> {
+    if let Some(path) = env::var_os(CONFIG_PATH_VARIABLE) {
+        return Ok(path.into());
+    }
+    let mut path = BaseDirs::new()
+        .context("could not locate system directories")?
+        .config_dir()
+        .to_path_buf();
+    path.push("level-99");
+    path.push("config.toml");
+    Ok(path)
+}
@@ -1,38 +1,80 @@
 use anyhow::*;
+use parking_lot::RwLock;
 use serenity::model::id::{ChannelId, UserId};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 
 pub mod pool;
 pub mod quiz;
+pub mod stats;
 pub mod team;
 
+#[cfg(test)]
+mod tests;
+
 use self::quiz::definition::QuizDefinition;
+use self::quiz::settings::SettingsOverrides;
+use self::quiz::snapshot::Snapshot;
 use self::quiz::Quiz;
-use self::team::{sanitize_name, Team, TeamId, TeamsHandle};
-use crate::output::{GameOutput, Message, Recipient};
+use self::stats::{PlayerStats, Stats, StatsHandle};
+use self::team::{
+    sanitize_name, undo_last_change, ScoreChange, ScoreReason, Team, TeamId, TeamsHandle,
+};
+use crate::output::{Event, GameOutput, Message, Recipient};
 
 enum Phase<O: GameOutput> {
     Startup,
     Setup,
-    Quiz(Quiz<O>),
+    Quiz(Box<Quiz<O>>),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GameMode {
+    Teams,
+    // Every player competes on their own, without joining a team
+    Solo,
+}
+
+impl FromStr for GameMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "teams" => Ok(GameMode::Teams),
+            "solo" => Ok(GameMode::Solo),
+            _ => Err(anyhow!("Game mode must be `teams` or `solo`")),
+        }
+    }
 }
 
 pub struct Game<O: GameOutput> {
     current_phase: Phase<O>,
+    mode: GameMode,
     teams: TeamsHandle,
+    stats: StatsHandle,
     output: O,
     paused: bool,
+    snapshot_path: Option<PathBuf>,
 }
 
 impl<O: GameOutput + Clone> Game<O> {
-    pub fn new(output: O, teams: TeamsHandle) -> Self {
+    pub fn new(
+        output: O,
+        teams: TeamsHandle,
+        snapshot_path: Option<PathBuf>,
+        stats_path: Option<PathBuf>,
+    ) -> Self {
         let mut game = Game {
             current_phase: Phase::Startup,
+            mode: GameMode::Teams,
             paused: false,
             output,
             teams,
+            stats: Arc::new(RwLock::new(Stats::open(stats_path))),
+            snapshot_path,
         };
         game.set_current_phase(Phase::Setup);
         game
@@ -40,6 +82,8 @@ impl<O: GameOutput + Clone> Game<O> {
 
     fn set_current_phase(&mut self, phase: Phase<O>) {
         self.current_phase = phase;
+        // A quiz that was ended early never finishes its last phase, so its guesses are saved here
+        self.stats.write().save();
     }
 
     pub fn tick(&mut self, dt: Duration) {
@@ -55,18 +99,82 @@ impl<O: GameOutput + Clone> Game<O> {
         }
     }
 
-    pub fn begin(&mut self, quiz_path: &Path) -> Result<()> {
+    pub fn begin(&mut self, quiz_path: &Path, settings_overrides: SettingsOverrides) -> Result<()> {
         match &self.current_phase {
             Phase::Setup => {
                 let definition = QuizDefinition::open(quiz_path)?;
-                let quiz = Quiz::new(definition, self.teams.clone(), self.output.clone());
-                self.set_current_phase(Phase::Quiz(quiz));
+                definition
+                    .get_settings()
+                    .merge(&settings_overrides)
+                    .check()?;
+                self.output.record(&Event::QuizBegins {
+                    quiz_path: definition.get_path().to_path_buf(),
+                    teams: self.teams.read().clone(),
+                });
+                let quiz = Quiz::new(
+                    definition,
+                    settings_overrides,
+                    self.teams.clone(),
+                    self.stats.clone(),
+                    self.output.clone(),
+                    self.snapshot_path.clone(),
+                )?;
+                self.set_current_phase(Phase::Quiz(Box::new(quiz)));
                 Ok(())
             }
             _ => Err(anyhow!("Cannot call begin outside of setup phase")),
         }
     }
 
+    fn read_snapshot(&self) -> Result<Snapshot> {
+        let snapshot_path = self
+            .snapshot_path
+            .as_ref()
+            .context("Quiz snapshots are not available")?;
+        Snapshot::read(snapshot_path)
+    }
+
+    // The teams of the interrupted quiz only replace the current ones once the snapshot is known to be valid,
+    // and they get their channels before the quiz says anything to them
+    pub fn resume<F>(&mut self, get_team_channels: F) -> Result<()>
+    where
+        F: FnOnce(&Vec<Team>) -> Result<HashMap<TeamId, ChannelId>>,
+    {
+        match &self.current_phase {
+            Phase::Setup => {
+                let snapshot = self.read_snapshot()?;
+                let definition = QuizDefinition::open(&snapshot.quiz_path).with_context(|| {
+                    format!("Could not open quiz with path {:?}", snapshot.quiz_path)
+                })?;
+                let teams = snapshot.teams.clone();
+                let mut quiz = Quiz::resume(
+                    definition,
+                    snapshot,
+                    self.teams.clone(),
+                    self.stats.clone(),
+                    self.output.clone(),
+                    self.snapshot_path.clone(),
+                )?;
+                let channel_ids = get_team_channels(&teams)?;
+
+                *self.teams.write() = teams.clone();
+                self.output.update_team_channels(channel_ids);
+                self.output.record(&Event::QuizBegins {
+                    quiz_path: quiz.get_path().to_path_buf(),
+                    teams,
+                });
+                quiz.start();
+                self.set_current_phase(Phase::Quiz(Box::new(quiz)));
+                self.paused = false;
+                self.output.say(&Recipient::AllTeams, &Message::QuizResumed);
+                Ok(())
+            }
+            _ => Err(anyhow!(
+                "Cannot resume a quiz while another one is in progress"
+            )),
+        }
+    }
+
     pub fn end(&mut self) -> Result<()> {
         match &mut self.current_phase {
             Phase::Quiz(q) => {
@@ -78,6 +186,13 @@ impl<O: GameOutput + Clone> Game<O> {
         }
     }
 
+    pub fn continue_quiz(&mut self) -> Result<()> {
+        match &mut self.current_phase {
+            Phase::Quiz(q) => q.continue_without_failed_questions(),
+            _ => Err(anyhow!("There is no quiz in progress")),
+        }
+    }
+
     pub fn skip(&mut self) -> Result<()> {
         match &mut self.current_phase {
             Phase::Quiz(q) => {
@@ -88,14 +203,62 @@ impl<O: GameOutput + Clone> Game<O> {
         }
     }
 
+    pub fn get_mode(&self) -> GameMode {
+        self.mode
+    }
+
+    // Switching modes disbands every team since they only make sense in one of them
+    pub fn set_mode(&mut self, mode: GameMode) -> Result<()> {
+        match &self.current_phase {
+            Phase::Setup => {
+                if self.mode != mode {
+                    self.mode = mode;
+                    self.teams.write().clear();
+                }
+                Ok(())
+            }
+            _ => Err(anyhow!("Cannot change the game mode during a quiz")),
+        }
+    }
+
+    // Returns whether the player was not competing yet
+    pub fn add_solo_player(&mut self, player: UserId, name: &str) -> Result<bool> {
+        if self.mode != GameMode::Solo {
+            return Err(anyhow!("The game is not in solo mode"));
+        }
+        let team_id = TeamId::Player(player);
+        let mut teams = self.teams.write();
+        if let Some(team) = teams.iter_mut().find(|t| t.id == team_id) {
+            team.player_name = Some(name.to_owned());
+            return Ok(false);
+        }
+        let mut team = Team::new(team_id);
+        team.players.insert(player);
+        team.player_name = Some(name.to_owned());
+        teams.push(team);
+        Ok(true)
+    }
+
+    fn get_competitor(&self, player: UserId) -> Result<TeamId> {
+        match self.mode {
+            GameMode::Teams => self
+                .get_player_team(player)
+                .context("Player is not on a team"),
+            GameMode::Solo if self.is_solo_player(player) => Ok(TeamId::Player(player)),
+            GameMode::Solo => Err(anyhow!("Player has not joined the quiz")),
+        }
+    }
+
     pub fn guess(&mut self, player: UserId, guess: &str) -> Result<()> {
-        let team_id = self
-            .get_player_team(player)
-            .context("Player is not on a team")?;
+        let team_id = self.get_competitor(player)?;
 
         match &mut self.current_phase {
             Phase::Quiz(quiz) => {
-                quiz.guess(&team_id, guess)?;
+                // Solo players can join in the middle of a quiz
+                if self.mode == GameMode::Solo {
+                    quiz.admit(&team_id);
+                }
+                quiz.guess(&team_id, player, guess)?;
                 Ok(())
             }
             _ => Err(anyhow!("There is no quiz in progress")),
@@ -103,9 +266,7 @@ impl<O: GameOutput + Clone> Game<O> {
     }
 
     pub fn wager(&mut self, player: UserId, amount: u32) -> Result<()> {
-        let team_id = self
-            .get_player_team(player)
-            .context("Player is not on a team")?;
+        let team_id = self.get_competitor(player)?;
 
         match &mut self.current_phase {
             Phase::Quiz(quiz) => {
@@ -116,6 +277,14 @@ impl<O: GameOutput + Clone> Game<O> {
         }
     }
 
+    // Lets the quizmaster overrule the judgement of a guess for the current or the last question
+    pub fn rejudge_guess(&mut self, team_id: TeamId, is_correct: bool) -> Result<()> {
+        match &mut self.current_phase {
+            Phase::Quiz(quiz) => quiz.rejudge(&team_id, is_correct),
+            _ => Err(anyhow!("There is no quiz in progress")),
+        }
+    }
+
     pub fn disband_team(&mut self, team_name: &str) -> Result<()> {
         let team_name = sanitize_name(team_name)?;
         let team_id = TeamId::TeamName(team_name);
@@ -129,6 +298,9 @@ impl<O: GameOutput + Clone> Game<O> {
     }
 
     pub fn join_team(&mut self, player: UserId, team_name: &str) -> Result<()> {
+        if self.mode == GameMode::Solo {
+            return Err(anyhow!("There are no teams in solo mode"));
+        }
         let mut teams = self.teams.write();
 
         // Remove player from existing team
@@ -162,9 +334,14 @@ impl<O: GameOutput + Clone> Game<O> {
                 .iter_mut()
                 .find(|t| t.id == team_id)
                 .context("Team not found")?;
-            team.update_score(delta);
+            team.update_score(delta, ScoreReason::Adjusted, None);
             team.score
         };
+        self.output.record(&Event::ScoreChange {
+            team_id: team_id.clone(),
+            delta,
+            score: new_score,
+        });
         self.output.say(
             &Recipient::AllTeams,
             &Message::TeamScoreAdjusted(team_id, new_score),
@@ -172,6 +349,71 @@ impl<O: GameOutput + Clone> Game<O> {
         Ok(())
     }
 
+    // Reverts the most recent score change, whether it came from a guess or from the quizmaster.
+    // Stats and contributions of the player who guessed are reverted as well, though their team still cannot guess again.
+    pub fn undo(&mut self) -> Result<()> {
+        let (change, new_score) = {
+            let mut teams = self.teams.write();
+            let change =
+                undo_last_change(&mut teams).context("There is no score change to undo")?;
+            let new_score = teams
+                .iter()
+                .find(|t| t.id == change.team_id)
+                .map(|t| t.score)
+                .unwrap_or_default();
+            (change, new_score)
+        };
+        if let Some(guess) = &change.guess {
+            let mut stats = self.stats.write();
+            match change.reason {
+                ScoreReason::Rejudged => stats.rejudge_guess(
+                    guess.player,
+                    &guess.category,
+                    !guess.is_correct,
+                    -change.delta,
+                ),
+                _ => stats.forget_guess(
+                    guess.player,
+                    &guess.category,
+                    guess.is_correct,
+                    change.delta,
+                ),
+            }
+            match &mut self.current_phase {
+                Phase::Quiz(quiz) => quiz.forget_contribution(guess.player, change.delta),
+                // Without a quiz in progress, no phase will end to save the stats
+                _ => stats.save(),
+            }
+        }
+        self.output.record(&Event::ScoreChange {
+            team_id: change.team_id.clone(),
+            delta: -change.delta,
+            score: new_score,
+        });
+        self.output.say(
+            &Recipient::AllTeams,
+            &Message::ScoreChangeUndone(change.team_id, change.delta, new_score),
+        );
+        Ok(())
+    }
+
+    pub fn get_score_history(&self, team_id: &TeamId) -> Result<Vec<ScoreChange>> {
+        self.teams
+            .read()
+            .iter()
+            .find(|t| t.id == *team_id)
+            .map(|t| t.history.clone())
+            .context("Team not found")
+    }
+
+    pub fn get_player_stats(&self, player: UserId) -> PlayerStats {
+        self.stats.read().get_player(player)
+    }
+
+    pub fn get_leaderboard(&self, max_entries: usize) -> Vec<(UserId, PlayerStats)> {
+        self.stats.read().get_leaderboard(max_entries)
+    }
+
     pub fn reset_teams(&mut self) {
         self.teams.write().clear();
         self.output.say(&Recipient::AllTeams, &Message::TeamsReset);
@@ -181,7 +423,7 @@ impl<O: GameOutput + Clone> Game<O> {
         {
             let mut teams = self.teams.write();
             for team in teams.iter_mut() {
-                team.score = 0;
+                team.reset_score();
             }
         }
         self.output.say(&Recipient::AllTeams, &Message::ScoresReset);
@@ -206,6 +448,16 @@ impl<O: GameOutput + Clone> Game<O> {
         self.output.update_team_channels(channel_ids);
     }
 
+    pub fn update_quizmaster_channel(&self, channel_id: ChannelId) {
+        self.output.update_quizmaster_channel(channel_id);
+    }
+
+    // Whether the player already competes on their own in this game
+    pub fn is_solo_player(&self, player: UserId) -> bool {
+        let team_id = TeamId::Player(player);
+        self.mode == GameMode::Solo && self.teams.read().iter().any(|t| t.id == team_id)
+    }
+
     fn get_player_team(&self, player: UserId) -> Option<TeamId> {
         let teams = self.teams.read();
         teams
@@ -1,26 +1,27 @@
 use parking_lot::RwLock;
 use serenity::client::Context as SerenityContext;
-use serenity::model::id::GuildId;
+use serenity::model::id::{GuildId, UserId};
 use serenity::prelude::Mutex;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
+use crate::game::quiz::snapshot;
+use crate::game::stats;
 use crate::game::Game;
 use crate::output::discord::{DiscordGameOutput, GuildOutput};
+use crate::output::recorder::{self, Recorder};
 use crate::DiscordOutputManager;
 
+pub type GameHandle = Arc<Mutex<Game<Recorder<DiscordGameOutput>>>>;
+
 #[derive(Default)]
 pub struct Pool {
-    games: RwLock<HashMap<GuildId, Arc<Mutex<Game<DiscordGameOutput>>>>>,
+    games: RwLock<HashMap<GuildId, GameHandle>>,
 }
 
 impl Pool {
-    pub fn get_game(
-        &self,
-        ctx: &SerenityContext,
-        guild_id: GuildId,
-    ) -> Arc<Mutex<Game<DiscordGameOutput>>> {
+    pub fn get_game(&self, ctx: &SerenityContext, guild_id: GuildId) -> GameHandle {
         let game_exists = {
             let map = self.games.read();
             map.contains_key(&guild_id)
@@ -36,7 +37,11 @@ impl Pool {
             let teams = Arc::new(RwLock::new(Vec::new()));
             let guild_output = GuildOutput::new(guild_id, &discord_output);
             let output = DiscordGameOutput::new(guild_output, teams.clone());
-            let game = Game::new(output, teams);
+            let log_directory = recorder::get_log_directory(&guild_id.to_string()).ok();
+            let output = Recorder::new(output, log_directory);
+            let snapshot_path = snapshot::get_snapshot_path(&guild_id.to_string()).ok();
+            let stats_path = stats::get_stats_path(&guild_id.to_string()).ok();
+            let game = Game::new(output, teams, snapshot_path, stats_path);
             let mut map = self.games.write();
             map.insert(guild_id, Arc::new(Mutex::new(game)));
         }
@@ -44,6 +49,20 @@ impl Pool {
         Arc::clone(map.get(&guild_id).unwrap())
     }
 
+    // Private messages are not tied to a server, so they only go to a solo game the player already joined
+    pub fn find_solo_game(&self, player: UserId) -> Option<(GuildId, GameHandle)> {
+        // Games are locked one at a time without holding on to the pool
+        let games: Vec<(GuildId, GameHandle)> = self
+            .games
+            .read()
+            .iter()
+            .map(|(guild_id, game)| (*guild_id, Arc::clone(game)))
+            .collect();
+        games
+            .into_iter()
+            .find(|(_guild_id, game)| game.lock().is_solo_player(player))
+    }
+
     pub fn tick(&self, dt: Duration) {
         let map = self.games.read();
         for (_channel, game) in map.iter() {
@@ -1,32 +1,129 @@
 use anyhow::*;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
-use std::fs::File;
-use std::path::Path;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
 
 pub mod question;
+pub mod structured;
+pub mod validate;
 
-pub use question::{Question, RawQuestion};
+use crate::game::quiz::settings::SettingsOverrides;
+use crate::preload;
+pub use question::{GuessJudgement, Question, RawQuestion};
+use structured::StructuredQuiz;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    Csv,
+    Json,
+    Toml,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Format {
+        let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("json") => Format::Json,
+            Some("toml") => Format::Toml,
+            _ => Format::Csv,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct QuizMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub description: Option<String>,
+}
 
 #[derive(Debug)]
 pub struct QuizDefinition {
+    path: PathBuf,
+    metadata: Option<QuizMetadata>,
+    settings: SettingsOverrides,
     questions: HashSet<Question>,
 }
 
 impl QuizDefinition {
     pub fn open(source: &Path) -> Result<QuizDefinition> {
-        let mut questions = HashSet::new();
+        let mut raw_questions = Vec::new();
+        let mut metadata = None;
+        let mut settings = SettingsOverrides::default();
+
+        match Format::from_path(source) {
+            Format::Csv => {
+                let file = File::open(source)?;
+                let mut csv_reader = csv::Reader::from_reader(file);
+                for question in csv_reader.deserialize() {
+                    let raw_question: RawQuestion = question?;
+                    raw_questions.push(raw_question);
+                }
+            }
+            format => {
+                let content = fs::read_to_string(source)?;
+                let quiz = match format {
+                    Format::Json => StructuredQuiz::from_json(&content)?,
+                    _ => StructuredQuiz::from_toml(&content)?,
+                };
+                for (_category, _index, raw_question) in quiz.get_raw_questions() {
+                    raw_questions.push(raw_question);
+                }
+                quiz.settings.check()?;
+                metadata = quiz.metadata;
+                settings = quiz.settings;
+            }
+        }
 
-        let file = File::open(source)?;
-        let mut csv_reader = csv::Reader::from_reader(file);
-        for question in csv_reader.deserialize() {
-            let raw_question: RawQuestion = question?;
-            questions.insert(raw_question.into());
+        let sidecar_path = get_settings_sidecar_path(source);
+        if sidecar_path.exists() {
+            let content = fs::read_to_string(&sidecar_path)?;
+            let sidecar_settings = SettingsOverrides::from_toml(&content)
+                .with_context(|| format!("Invalid settings file {:?}", sidecar_path))?;
+            settings = settings.merge(&sidecar_settings);
+            settings
+                .check()
+                .with_context(|| format!("Invalid settings file {:?}", sidecar_path))?;
         }
 
-        Ok(QuizDefinition { questions })
+        let path = source.canonicalize()?;
+        let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let questions = raw_questions
+            .into_iter()
+            .map(|mut raw_question| {
+                if preload::is_local_path(&raw_question.url, base_dir) {
+                    raw_question.url = preload::resolve_local_url(&raw_question.url, base_dir);
+                }
+                raw_question.into()
+            })
+            .collect();
+        Ok(QuizDefinition {
+            path,
+            metadata,
+            settings,
+            questions,
+        })
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get_metadata(&self) -> Option<&QuizMetadata> {
+        self.metadata.as_ref()
+    }
+
+    pub fn get_settings(&self) -> &SettingsOverrides {
+        &self.settings
     }
 
     pub fn get_questions(&self) -> &HashSet<Question> {
         &self.questions
     }
 }
+
+// Settings for `quiz.csv` can be overridden by a `quiz.settings.toml` file next to it
+pub fn get_settings_sidecar_path(source: &Path) -> PathBuf {
+    source.with_extension("settings.toml")
+}
@@ -1,16 +1,18 @@
 use lazy_static::lazy_static;
 use regex::Regex;
 use serde::de;
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::hash::{Hash, Hasher};
 use std::time::Duration;
 use unidecode::unidecode;
 
+use crate::preload::Song;
+
 lazy_static! {
     static ref FORBIDDEN_GUESS_CHARACTERS_REGEX: Regex = Regex::new("[^a-z0-9]").unwrap();
 }
 
-fn sanitize(answer: &str) -> String {
+pub fn sanitize(answer: &str) -> String {
     let answer = unidecode(answer);
     FORBIDDEN_GUESS_CHARACTERS_REGEX
         .replace_all(&answer.to_lowercase(), "")
@@ -35,33 +37,165 @@ where
     }
 }
 
+// CSV quizzes list every acceptable answer in a single cell, separated by `|`
+fn answers_from_string<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let answers = String::deserialize(deserializer)?;
+    if answers.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(answers.split('|').map(|a| a.to_owned()).collect())
+}
+
+// Number of typos tolerated in a guess, scaling with answer length
+fn typo_tolerance(answer_length: usize) -> usize {
+    answer_length / 5
+}
+
+// Guesses this close to an answer (but not within typo tolerance) get another chance
+fn close_tolerance(answer_length: usize) -> usize {
+    answer_length / 3
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut distances: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut previous_diagonal = distances[0];
+        distances[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous_diagonal + if a_char == *b_char { 0 } else { 1 };
+            previous_diagonal = distances[j + 1];
+            distances[j + 1] = substitution.min(distances[j] + 1).min(distances[j + 1] + 1);
+        }
+    }
+    distances[b.len()]
+}
+
 #[derive(Clone, Deserialize, Hash, PartialEq, Eq)]
 pub struct RawQuestion {
     pub url: String,
     pub answer: String,
-    pub acceptable_answers: Option<String>,
+    #[serde(default, deserialize_with = "answers_from_string")]
+    pub acceptable_answers: Vec<String>,
     pub category: String,
     pub score_value: u32,
     #[serde(deserialize_with = "bool_from_string")]
     pub challenge: bool,
+    #[serde(default)]
     pub duration_seconds: Option<u64>,
+    #[serde(default, deserialize_with = "bool_from_string")]
+    pub exact_match: bool,
+    #[serde(default)]
+    pub start_seconds: Option<u64>,
+    #[serde(default)]
+    pub end_seconds: Option<u64>,
+    #[serde(default, deserialize_with = "bool_from_string")]
+    pub steal: bool,
+    #[serde(default, deserialize_with = "bool_from_string")]
+    pub reveal: bool,
+    #[serde(default, rename = "final", deserialize_with = "bool_from_string")]
+    pub final_round: bool,
+    #[serde(default, deserialize_with = "bool_from_string")]
+    pub tiebreaker: bool,
+}
+
+#[cfg(test)]
+impl RawQuestion {
+    // Regular question for tests, which override the fields they care about
+    pub fn test() -> RawQuestion {
+        RawQuestion {
+            url: "example url".to_owned(),
+            answer: "example answer".to_owned(),
+            acceptable_answers: Vec::new(),
+            category: "example category".to_owned(),
+            score_value: 100,
+            challenge: false,
+            duration_seconds: None,
+            exact_match: false,
+            start_seconds: None,
+            end_seconds: None,
+            steal: false,
+            reveal: false,
+            final_round: false,
+            tiebreaker: false,
+        }
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GuessJudgement {
+    Correct,
+    Close,
+    Incorrect,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Question {
     pub url: String,
     pub answer: String,
-    pub acceptable_answers: Regex,
+    pub acceptable_answers: Vec<String>,
     pub category: String,
     pub score_value: u32,
+    #[serde(default)]
     pub challenge: bool,
+    #[serde(default)]
     pub duration: Option<Duration>,
+    #[serde(default)]
+    pub exact_match: bool,
+    #[serde(default)]
+    pub start: Option<Duration>,
+    #[serde(default)]
+    pub end: Option<Duration>,
+    // Other teams get a chance to steal the question after a wrong guess
+    #[serde(default)]
+    pub steal: bool,
+    // The song is revealed through clips of increasing length, worth fewer points each time
+    #[serde(default)]
+    pub reveal: bool,
+    // Held back for the final round, where every team with points wagers blind
+    #[serde(default)]
+    pub final_round: bool,
+    // Kept in reserve to break a tie between the winners
+    #[serde(default)]
+    pub tiebreaker: bool,
 }
 
 impl Question {
-    pub fn is_guess_correct(&self, guess: &str) -> bool {
+    pub fn get_song(&self) -> Song {
+        Song {
+            url: self.url.clone(),
+            start: self.start,
+            end: self.end,
+        }
+    }
+
+    // Final and tiebreaker questions are kept out of the regular rounds
+    pub fn is_reserved(&self) -> bool {
+        self.final_round || self.tiebreaker
+    }
+
+    pub fn judge_guess(&self, guess: &str) -> GuessJudgement {
         let sanitized_guess = sanitize(guess);
-        self.acceptable_answers.is_match(&sanitized_guess)
+        if self.acceptable_answers.contains(&sanitized_guess) {
+            return GuessJudgement::Correct;
+        }
+        if self.exact_match {
+            return GuessJudgement::Incorrect;
+        }
+
+        let mut judgement = GuessJudgement::Incorrect;
+        for answer in &self.acceptable_answers {
+            let distance = edit_distance(answer, &sanitized_guess);
+            if distance <= typo_tolerance(answer.len()) {
+                return GuessJudgement::Correct;
+            } else if distance <= close_tolerance(answer.len()) {
+                judgement = GuessJudgement::Close;
+            }
+        }
+        judgement
     }
 }
 
@@ -87,37 +221,30 @@ impl From<RawQuestion> for Question {
         // Gather all answers
         let mut acceptable_answers = Vec::new();
         acceptable_answers.push(raw_question.answer.to_owned());
-        if let Some(answers) = raw_question.acceptable_answers {
-            for answer in answers.split("|") {
-                acceptable_answers.push(answer.to_owned());
-            }
-        }
+        acceptable_answers.extend(raw_question.acceptable_answers);
 
         // Sanitize
         let acceptable_answers: Vec<String> = acceptable_answers
             .iter()
-            .filter_map(|answer| {
-                let sanitized = sanitize(answer);
-                if sanitized.is_empty() {
-                    None
-                } else {
-                    Some(format!("({})", sanitized))
-                }
-            })
+            .map(|answer| sanitize(answer))
+            .filter(|answer| !answer.is_empty())
             .collect();
 
-        // Turn into a regex
-        let regex_to_parse = format!("^({})$", acceptable_answers.join("|"));
-        let acceptable_answers = Regex::new(&regex_to_parse).unwrap();
-
         Question {
             url: raw_question.url,
             answer: raw_question.answer,
-            acceptable_answers: acceptable_answers,
+            acceptable_answers,
             category: raw_question.category,
             score_value: raw_question.score_value,
             challenge: raw_question.challenge,
             duration: raw_question.duration_seconds.map(Duration::from_secs),
+            exact_match: raw_question.exact_match,
+            start: raw_question.start_seconds.map(Duration::from_secs),
+            end: raw_question.end_seconds.map(Duration::from_secs),
+            steal: raw_question.steal,
+            reveal: raw_question.reveal,
+            final_round: raw_question.final_round,
+            tiebreaker: raw_question.tiebreaker,
         }
     }
 }
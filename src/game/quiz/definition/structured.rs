@@ -0,0 +1,91 @@
+use anyhow::*;
+use serde::Deserialize;
+
+use crate::game::quiz::definition::{QuizMetadata, RawQuestion};
+use crate::game::quiz::settings::SettingsOverrides;
+
+// JSON and TOML quizzes group questions by category instead of repeating it on every question
+#[derive(Deserialize)]
+pub struct StructuredQuiz {
+    pub metadata: Option<QuizMetadata>,
+    #[serde(default)]
+    pub settings: SettingsOverrides,
+    pub categories: Vec<StructuredCategory>,
+}
+
+#[derive(Deserialize)]
+pub struct StructuredCategory {
+    pub name: String,
+    pub questions: Vec<StructuredQuestion>,
+}
+
+#[derive(Deserialize)]
+pub struct StructuredQuestion {
+    pub url: String,
+    pub answer: String,
+    #[serde(default)]
+    pub acceptable_answers: Vec<String>,
+    pub score_value: u32,
+    #[serde(default)]
+    pub challenge: bool,
+    #[serde(default)]
+    pub duration_seconds: Option<u64>,
+    #[serde(default)]
+    pub exact_match: bool,
+    #[serde(default)]
+    pub start_seconds: Option<u64>,
+    #[serde(default)]
+    pub end_seconds: Option<u64>,
+    #[serde(default)]
+    pub steal: bool,
+    #[serde(default)]
+    pub reveal: bool,
+    #[serde(default, rename = "final")]
+    pub final_round: bool,
+    #[serde(default)]
+    pub tiebreaker: bool,
+}
+
+impl StructuredQuestion {
+    pub fn to_raw_question(&self, category: &str) -> RawQuestion {
+        RawQuestion {
+            url: self.url.clone(),
+            answer: self.answer.clone(),
+            acceptable_answers: self.acceptable_answers.clone(),
+            category: category.to_owned(),
+            score_value: self.score_value,
+            challenge: self.challenge,
+            duration_seconds: self.duration_seconds,
+            exact_match: self.exact_match,
+            start_seconds: self.start_seconds,
+            end_seconds: self.end_seconds,
+            steal: self.steal,
+            reveal: self.reveal,
+            final_round: self.final_round,
+            tiebreaker: self.tiebreaker,
+        }
+    }
+}
+
+impl StructuredQuiz {
+    pub fn from_json(content: &str) -> Result<StructuredQuiz> {
+        serde_json::from_str(content).context("Could not parse JSON quiz")
+    }
+
+    pub fn from_toml(content: &str) -> Result<StructuredQuiz> {
+        toml::from_str(content).context("Could not parse TOML quiz")
+    }
+
+    pub fn get_raw_questions(&self) -> Vec<(&StructuredCategory, usize, RawQuestion)> {
+        self.categories
+            .iter()
+            .flat_map(|category| {
+                category
+                    .questions
+                    .iter()
+                    .enumerate()
+                    .map(move |(index, q)| (category, index, q.to_raw_question(&category.name)))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,309 @@
+use anyhow::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+use crate::game::quiz::definition::question::{sanitize, RawQuestion};
+use crate::game::quiz::definition::structured::StructuredQuiz;
+use crate::game::quiz::definition::{get_settings_sidecar_path, Format};
+use crate::game::quiz::settings::SettingsOverrides;
+use crate::preload;
+
+#[cfg(test)]
+mod tests;
+
+// CSV problems point at a line, JSON and TOML problems point at a question within its category
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Location {
+    Line(u64),
+    Question { category: String, index: usize },
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Line(line) => write!(f, "line {}", line),
+            Location::Question { category, index } => {
+                write!(f, "category `{}`, question {}", category, index + 1)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Problem {
+    pub location: Option<Location>,
+    pub description: String,
+}
+
+impl Problem {
+    fn new(location: Option<Location>, description: String) -> Self {
+        Problem {
+            location,
+            description,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{}: {}", location, self.description),
+            None => write!(f, "{}", self.description),
+        }
+    }
+}
+
+pub fn validate(source: &Path) -> Result<Vec<Problem>> {
+    // Local audio files are relative to the quiz file
+    let base_dir = source.parent().unwrap_or_else(|| Path::new(""));
+    let mut problems = match Format::from_path(source) {
+        Format::Csv => validate_csv(File::open(source)?, base_dir),
+        Format::Json => validate_json(&fs::read_to_string(source)?, base_dir),
+        Format::Toml => validate_toml(&fs::read_to_string(source)?, base_dir),
+    };
+    let sidecar_path = get_settings_sidecar_path(source);
+    if sidecar_path.exists() {
+        if let Err(e) = SettingsOverrides::from_toml(&fs::read_to_string(&sidecar_path)?) {
+            problems.push(Problem::new(
+                None,
+                format!("invalid settings file {:?}: {:#}", sidecar_path, e),
+            ));
+        }
+    }
+    Ok(problems)
+}
+
+pub fn validate_json(content: &str, base_dir: &Path) -> Vec<Problem> {
+    match serde_json::from_str(content) {
+        Ok(quiz) => validate_structured(&quiz, base_dir),
+        Err(e) => vec![Problem::new(
+            Some(Location::Line(e.line() as u64)),
+            format!("could not read quiz: {}", e),
+        )],
+    }
+}
+
+pub fn validate_toml(content: &str, base_dir: &Path) -> Vec<Problem> {
+    match toml::from_str(content) {
+        Ok(quiz) => validate_structured(&quiz, base_dir),
+        Err(e) => {
+            let location = e
+                .line_col()
+                .map(|(line, _col)| Location::Line(line as u64 + 1));
+            vec![Problem::new(
+                location,
+                format!("could not read quiz: {}", e),
+            )]
+        }
+    }
+}
+
+fn validate_structured(quiz: &StructuredQuiz, base_dir: &Path) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    if let Err(e) = quiz.settings.check() {
+        problems.push(Problem::new(None, format!("invalid settings: {}", e)));
+    }
+    let questions = quiz
+        .get_raw_questions()
+        .into_iter()
+        .map(|(category, index, raw_question)| {
+            let location = Location::Question {
+                category: category.name.clone(),
+                index,
+            };
+            (location, raw_question)
+        })
+        .collect();
+    validate_questions(questions, problems, base_dir)
+}
+
+pub fn validate_csv<R: Read>(reader: R, base_dir: &Path) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut questions: Vec<(Location, RawQuestion)> = Vec::new();
+
+    let mut csv_reader = csv::Reader::from_reader(reader);
+    let headers = match csv_reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(e) => {
+            problems.push(Problem::new(
+                Some(Location::Line(1)),
+                format!("could not read column names: {}", e),
+            ));
+            return problems;
+        }
+    };
+
+    for record in csv_reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let location = e.position().map(|p| Location::Line(p.line()));
+                problems.push(Problem::new(location, format!("could not read row: {}", e)));
+                continue;
+            }
+        };
+        let line = record.position().map(|p| p.line()).unwrap_or_default();
+        match record.deserialize::<RawQuestion>(Some(&headers)) {
+            Ok(raw_question) => questions.push((Location::Line(line), raw_question)),
+            Err(e) => problems.push(Problem::new(
+                Some(Location::Line(line)),
+                format!("invalid question: {}", e),
+            )),
+        }
+    }
+
+    validate_questions(questions, problems, base_dir)
+}
+
+fn validate_questions(
+    questions: Vec<(Location, RawQuestion)>,
+    mut problems: Vec<Problem>,
+    base_dir: &Path,
+) -> Vec<Problem> {
+    for (location, question) in &questions {
+        if preload::is_local_path(&question.url, base_dir) {
+            let path = base_dir.join(preload::local_url_to_path(&question.url));
+            if !path.is_file() {
+                problems.push(Problem::new(
+                    Some(location.clone()),
+                    format!("audio file {:?} does not exist", path),
+                ));
+            }
+        } else if preload::url_to_path(&question.url).is_err() && !question.url.contains("://") {
+            problems.push(Problem::new(
+                Some(location.clone()),
+                format!(
+                    "`{}` is neither a Youtube URL nor an existing audio file",
+                    question.url
+                ),
+            ));
+        } else if preload::url_to_path(&question.url).is_err() {
+            problems.push(Problem::new(
+                Some(location.clone()),
+                format!("no video ID could be found in URL `{}`", question.url),
+            ));
+        }
+        if sanitize(&question.answer).is_empty() {
+            problems.push(Problem::new(
+                Some(location.clone()),
+                format!("answer `{}` has no letters or digits", question.answer),
+            ));
+        }
+        if let (Some(start), Some(end)) = (question.start_seconds, question.end_seconds) {
+            if start >= end {
+                problems.push(Problem::new(
+                    Some(location.clone()),
+                    format!(
+                        "excerpt ends at {} seconds, before it starts at {} seconds",
+                        end, start
+                    ),
+                ));
+            }
+        }
+        if question.steal && question.challenge {
+            problems.push(Problem::new(
+                Some(location.clone()),
+                "challenge questions cannot be stolen".to_owned(),
+            ));
+        }
+        if question.final_round && (question.challenge || question.steal) {
+            problems.push(Problem::new(
+                Some(location.clone()),
+                "the final question cannot be a challenge or be stolen".to_owned(),
+            ));
+        }
+        if question.tiebreaker && (question.challenge || question.final_round) {
+            problems.push(Problem::new(
+                Some(location.clone()),
+                "tiebreaker questions cannot be a challenge or the final question".to_owned(),
+            ));
+        }
+        for answer in question
+            .acceptable_answers
+            .iter()
+            .filter(|a| sanitize(a).is_empty())
+        {
+            problems.push(Problem::new(
+                Some(location.clone()),
+                format!("acceptable answer `{}` has no letters or digits", answer),
+            ));
+        }
+    }
+
+    let mut first_occurrences: HashMap<(&str, &str, u32), &Location> = HashMap::new();
+    for (location, question) in &questions {
+        let key = (
+            question.url.as_str(),
+            question.category.as_str(),
+            question.score_value,
+        );
+        match first_occurrences.get(&key) {
+            Some(first_location) => problems.push(Problem::new(
+                Some(location.clone()),
+                format!(
+                    "same URL, category and score value as the question at {}",
+                    first_location
+                ),
+            )),
+            None => {
+                first_occurrences.insert(key, location);
+            }
+        }
+    }
+
+    let mut final_questions = questions.iter().filter(|(_location, q)| q.final_round);
+    if let Some((first_location, _question)) = final_questions.next() {
+        for (location, _question) in final_questions {
+            problems.push(Problem::new(
+                Some(location.clone()),
+                format!("the final question was already set at {}", first_location),
+            ));
+        }
+    }
+
+    // Final and tiebreaker questions are played on their own, so they can have a category of their own
+    // and do not count towards the regular rounds
+    let regular_questions: Vec<&(Location, RawQuestion)> = questions
+        .iter()
+        .filter(|(_location, q)| !q.final_round && !q.tiebreaker)
+        .collect();
+    let mut category_sizes: HashMap<&str, usize> = HashMap::new();
+    for (_location, question) in &regular_questions {
+        *category_sizes
+            .entry(question.category.as_str())
+            .or_default() += 1;
+    }
+    for (location, question) in &regular_questions {
+        if category_sizes.get(question.category.as_str()) == Some(&1) {
+            problems.push(Problem::new(
+                Some(location.clone()),
+                format!(
+                    "category `{}` only contains a single question",
+                    question.category
+                ),
+            ));
+        }
+    }
+
+    if questions.is_empty() && problems.is_empty() {
+        problems.push(Problem::new(
+            None,
+            "quiz does not contain any question".to_owned(),
+        ));
+    }
+
+    if regular_questions.iter().all(|(_location, q)| q.challenge) {
+        for (location, _question) in &regular_questions {
+            problems.push(Problem::new(
+                Some(location.clone()),
+                "challenge question in a quiz without any normal question".to_owned(),
+            ));
+        }
+    }
+
+    problems.sort_by(|a, b| a.location.cmp(&b.location));
+    problems
+}
@@ -0,0 +1,244 @@
+use super::*;
+use crate::game::quiz::definition::Question;
+
+const HEADER: &str = "url,answer,category,score_value,acceptable_answers,challenge\n";
+
+fn validate_rows(rows: &str) -> Vec<Problem> {
+    let csv = format!("{}{}", HEADER, rows);
+    validate_csv(csv.as_bytes(), Path::new("."))
+}
+
+fn lines(problems: &[Problem]) -> Vec<Option<u64>> {
+    problems
+        .iter()
+        .map(|p| match p.location {
+            Some(Location::Line(line)) => Some(line),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn accepts_valid_quiz() {
+    let problems = validate_rows(
+        "https://www.youtube.com/watch?v=aaa,Answer A,Category,100,,\n\
+         https://www.youtube.com/watch?v=bbb,Answer B,Category,200,,TRUE\n",
+    );
+    assert!(problems.is_empty());
+}
+
+#[test]
+fn reports_missing_score_value() {
+    let problems = validate_rows(
+        "https://www.youtube.com/watch?v=aaa,Answer A,Category,100,,\n\
+         https://www.youtube.com/watch?v=bbb,Answer B,Category,,,\n\
+         https://www.youtube.com/watch?v=ccc,Answer C,Category,300,,\n",
+    );
+    assert_eq!(lines(&problems), [Some(3)]);
+}
+
+#[test]
+fn reports_url_without_video_id() {
+    let problems = validate_rows(
+        "https://www.youtube.com/watch?v=aaa,Answer A,Category,100,,\n\
+         https://example.com/song,Answer B,Category,200,,\n",
+    );
+    assert_eq!(lines(&problems), [Some(3)]);
+}
+
+#[test]
+fn reports_duplicate_questions() {
+    let problems = validate_rows(
+        "https://www.youtube.com/watch?v=aaa,Answer A,Category,100,,\n\
+         https://www.youtube.com/watch?v=bbb,Answer B,Category,200,,\n\
+         https://www.youtube.com/watch?v=aaa,Answer C,Category,100,,\n",
+    );
+    assert_eq!(lines(&problems), [Some(4)]);
+}
+
+#[test]
+fn reports_single_question_categories() {
+    let problems = validate_rows(
+        "https://www.youtube.com/watch?v=aaa,Answer A,Category,100,,\n\
+         https://www.youtube.com/watch?v=bbb,Answer B,Category,200,,\n\
+         https://www.youtube.com/watch?v=ccc,Answer C,Lonely,100,,\n",
+    );
+    assert_eq!(lines(&problems), [Some(4)]);
+}
+
+#[test]
+fn reports_quiz_with_only_challenge_questions() {
+    let problems = validate_rows(
+        "https://www.youtube.com/watch?v=aaa,Answer A,Category,100,,TRUE\n\
+         https://www.youtube.com/watch?v=bbb,Answer B,Category,200,,TRUE\n",
+    );
+    assert_eq!(lines(&problems), [Some(2), Some(3)]);
+}
+
+#[test]
+fn reports_answers_without_letters_or_digits() {
+    let problems = validate_rows(
+        "https://www.youtube.com/watch?v=aaa,???,Category,100,,\n\
+         https://www.youtube.com/watch?v=bbb,Answer B,Category,200,Other|!!,\n",
+    );
+    assert_eq!(lines(&problems), [Some(2), Some(3)]);
+}
+
+#[test]
+fn reports_excerpts_ending_before_they_start() {
+    let csv = "url,answer,category,score_value,challenge,start_seconds,end_seconds\n\
+               https://www.youtube.com/watch?v=aaa,Answer A,Category,100,,30,60\n\
+               https://www.youtube.com/watch?v=bbb,Answer B,Category,200,,60,30\n";
+    let problems = validate_csv(csv.as_bytes(), Path::new("."));
+    assert_eq!(lines(&problems), [Some(3)]);
+}
+
+#[test]
+fn reports_challenge_questions_that_can_be_stolen() {
+    let csv = "url,answer,category,score_value,challenge,steal\n\
+               https://www.youtube.com/watch?v=aaa,Answer A,Category,100,,TRUE\n\
+               https://www.youtube.com/watch?v=bbb,Answer B,Category,200,TRUE,TRUE\n";
+    let problems = validate_csv(csv.as_bytes(), Path::new("."));
+    assert_eq!(lines(&problems), [Some(3)]);
+}
+
+#[test]
+fn reports_extra_final_questions() {
+    let csv = "url,answer,category,score_value,challenge,final\n\
+               https://www.youtube.com/watch?v=aaa,Answer A,Category,100,,\n\
+               https://www.youtube.com/watch?v=bbb,Answer B,Category,200,,\n\
+               https://www.youtube.com/watch?v=ccc,Answer C,Finale,500,,TRUE\n\
+               https://www.youtube.com/watch?v=ddd,Answer D,Finale,500,TRUE,TRUE\n";
+    let problems = validate_csv(csv.as_bytes(), Path::new("."));
+    assert_eq!(lines(&problems), [Some(5), Some(5)]);
+}
+
+#[test]
+fn accepts_final_question_in_its_own_category() {
+    let csv = "url,answer,category,score_value,challenge,final\n\
+               https://www.youtube.com/watch?v=aaa,Answer A,Category,100,,\n\
+               https://www.youtube.com/watch?v=bbb,Answer B,Category,200,,\n\
+               https://www.youtube.com/watch?v=ccc,Answer C,Finale,500,,TRUE\n";
+    assert!(validate_csv(csv.as_bytes(), Path::new(".")).is_empty());
+}
+
+#[test]
+fn reports_tiebreaker_challenges() {
+    let csv = "url,answer,category,score_value,challenge,tiebreaker\n\
+               https://www.youtube.com/watch?v=aaa,Answer A,Category,100,,\n\
+               https://www.youtube.com/watch?v=bbb,Answer B,Category,200,,\n\
+               https://www.youtube.com/watch?v=ccc,Answer C,Reserve,100,,TRUE\n\
+               https://www.youtube.com/watch?v=ddd,Answer D,Other Reserve,100,TRUE,TRUE\n";
+    let problems = validate_csv(csv.as_bytes(), Path::new("."));
+    assert_eq!(lines(&problems), [Some(5)]);
+}
+
+#[test]
+fn reports_challenges_without_normal_questions_besides_reserved_ones() {
+    let csv = "url,answer,category,score_value,challenge,final,tiebreaker\n\
+               https://www.youtube.com/watch?v=aaa,Answer A,Category,100,TRUE,,\n\
+               https://www.youtube.com/watch?v=bbb,Answer B,Category,200,TRUE,,\n\
+               https://www.youtube.com/watch?v=ccc,Answer C,Category,500,,TRUE,\n\
+               https://www.youtube.com/watch?v=ddd,Answer D,Category,100,,,TRUE\n";
+    let problems = validate_csv(csv.as_bytes(), Path::new("."));
+    assert_eq!(lines(&problems), [Some(2), Some(3)]);
+}
+
+#[test]
+fn accepts_valid_json_quiz() {
+    let problems = validate_json(
+        r#"{
+            "metadata": { "title": "Test quiz" },
+            "categories": [{
+                "name": "Category",
+                "questions": [
+                    { "url": "https://www.youtube.com/watch?v=aaa", "answer": "Answer A", "score_value": 100 },
+                    { "url": "https://www.youtube.com/watch?v=bbb", "answer": "Answer B", "score_value": 200,
+                      "acceptable_answers": ["Other", "Another"] }
+                ]
+            }]
+        }"#,
+        Path::new("."),
+    );
+    assert!(problems.is_empty());
+}
+
+#[test]
+fn reports_json_syntax_error_line() {
+    let problems = validate_json(
+        "{\n  \"categories\": [\n    { \"name\": }\n  ]\n}",
+        Path::new("."),
+    );
+    assert_eq!(lines(&problems), [Some(3)]);
+}
+
+#[test]
+fn reports_toml_questions_by_category_and_index() {
+    let problems = validate_toml(
+        r#"
+        [[categories]]
+        name = "Category"
+
+        [[categories.questions]]
+        url = "https://www.youtube.com/watch?v=aaa"
+        answer = "Answer A"
+        score_value = 100
+
+        [[categories.questions]]
+        url = "https://example.com/song"
+        answer = "Answer B"
+        score_value = 200
+        "#,
+        Path::new("."),
+    );
+    let locations: Vec<_> = problems.into_iter().map(|p| p.location).collect();
+    assert_eq!(
+        locations,
+        [Some(Location::Question {
+            category: "Category".to_owned(),
+            index: 1,
+        })]
+    );
+}
+
+#[test]
+fn reports_missing_local_audio_files() {
+    let base_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let csv = "url,answer,category,score_value,challenge\n\
+               assets/question.wav,Answer A,Category,100,\n\
+               file://assets/missing.wav,Answer B,Category,200,\n\
+               assets/missing.wav,Answer C,Category,300,\n";
+    let problems = validate_csv(csv.as_bytes(), base_dir);
+    assert_eq!(lines(&problems), [Some(3), Some(4)]);
+}
+
+#[test]
+fn accepts_youtube_urls_without_scheme() {
+    let problems = validate_rows(
+        "youtube.com/watch?v=abc123,Answer A,Category,100,,\n\
+         www.youtube.com/watch?v=abc123,Answer B,Category,200,,\n\
+         youtu.be/abc123,Answer C,Category,300,,\n",
+    );
+    assert!(problems.is_empty());
+}
+
+#[test]
+fn structured_acceptable_answers_are_not_split() {
+    let quiz = StructuredQuiz::from_json(
+        r#"{
+            "categories": [{
+                "name": "Rock",
+                "questions": [{
+                    "url": "https://www.youtube.com/watch?v=aaa",
+                    "answer": "Back in Black",
+                    "acceptable_answers": ["AC|DC"],
+                    "score_value": 100
+                }]
+            }]
+        }"#,
+    )
+    .unwrap();
+    let questions = quiz.get_raw_questions();
+    let question: Question = questions[0].2.clone().into();
+    assert_eq!(question.acceptable_answers, ["backinblack", "acdc"]);
+}
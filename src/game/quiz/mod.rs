@@ -1,17 +1,23 @@
 use anyhow::*;
-use std::collections::HashSet;
+use serenity::model::id::UserId;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use self::definition::*;
 use self::phase::*;
 use self::settings::*;
+use self::snapshot::*;
+use crate::game::stats::StatsHandle;
 use crate::game::{TeamId, TeamsHandle};
-use crate::output::{GameOutput, Message, Recipient};
+use crate::output::{Event, GameOutput, Message, Recipient};
+use crate::preload;
 
 pub mod assets;
 pub mod definition;
 mod phase;
-mod settings;
+pub mod settings;
+pub mod snapshot;
 
 trait State {
     fn on_begin(&mut self);
@@ -26,6 +32,8 @@ enum Phase<O: GameOutput> {
     Vote(VoteState<O>),
     Wager(WagerState<O>),
     Question(QuestionState<O>),
+    Steal(StealState<O>),
+    Tiebreaker(TiebreakerState<O>),
     Results(ResultsState<O>),
 }
 
@@ -37,6 +45,8 @@ impl<O: GameOutput> Phase<O> {
             Phase::Vote(s) => s,
             Phase::Wager(s) => s,
             Phase::Question(s) => s,
+            Phase::Steal(s) => s,
+            Phase::Tiebreaker(s) => s,
             Phase::Results(s) => s,
         }
     }
@@ -44,35 +54,217 @@ impl<O: GameOutput> Phase<O> {
 
 pub struct Quiz<O: GameOutput> {
     pub teams: TeamsHandle,
+    stats: StatsHandle,
     abort: bool,
     settings: Settings,
     current_phase: Phase<O>,
+    // Kept until the next question so the quizmaster can still overrule its judgements
+    previous_question: Option<QuestionState<O>>,
+    previous_steal: Option<StealState<O>>,
     initiative: Option<TeamId>,
+    // Outcome of the final round, kept secret until the winner is known
+    final_results: Option<(Question, FinalResults)>,
+    contributions: HashMap<UserId, i32>,
     remaining_questions: HashSet<Question>,
     max_question_score_value: u32,
+    quiz_path: PathBuf,
+    settings_overrides: SettingsOverrides,
+    snapshot_path: Option<PathBuf>,
+    resumed_phase: Option<Phase<O>>,
     output: O,
 }
 
 impl<O: GameOutput + Clone> Quiz<O> {
-    pub fn new(definition: QuizDefinition, teams: TeamsHandle, output: O) -> Self {
-        let settings: Settings = Default::default();
+    pub fn new(
+        definition: QuizDefinition,
+        settings_overrides: SettingsOverrides,
+        teams: TeamsHandle,
+        stats: StatsHandle,
+        output: O,
+        snapshot_path: Option<PathBuf>,
+    ) -> Result<Self> {
+        let settings = Self::make_settings(&definition, &settings_overrides);
         let questions = definition.get_questions().clone();
-        let song_urls = questions.iter().map(|q| q.url.to_owned()).collect();
         let max_question_score_value = questions.iter().map(|q| q.score_value).max().unwrap_or(0);
-        let startup_state =
-            StartupState::new(settings.startup_duration, &song_urls, output.clone());
+        let startup_state = StartupState::new(
+            settings.startup_duration,
+            questions.iter().cloned().collect(),
+            definition.get_metadata().cloned(),
+            output.clone(),
+        )?;
         let mut quiz = Quiz {
             abort: false,
             remaining_questions: questions,
-            current_phase: Phase::Startup(startup_state.clone()),
+            current_phase: Phase::Startup(startup_state),
+            previous_question: None,
+            previous_steal: None,
             max_question_score_value,
             initiative: None,
+            final_results: None,
+            contributions: HashMap::new(),
+            quiz_path: definition.get_path().to_path_buf(),
+            settings_overrides,
+            snapshot_path,
+            resumed_phase: None,
             output,
             settings,
             teams,
+            stats,
         };
-        quiz.set_current_phase(Phase::Startup(startup_state));
-        quiz
+        quiz.start();
+        Ok(quiz)
+    }
+
+    // A resumed quiz is only started once the game is ready for it
+    pub fn resume(
+        definition: QuizDefinition,
+        snapshot: Snapshot,
+        teams: TeamsHandle,
+        stats: StatsHandle,
+        output: O,
+        snapshot_path: Option<PathBuf>,
+    ) -> Result<Self> {
+        let settings = Self::make_settings(&definition, &snapshot.settings_overrides);
+        let find_question = |key: &QuestionKey| {
+            definition
+                .get_questions()
+                .iter()
+                .find(|q| QuestionKey::from(*q) == *key)
+                .cloned()
+                .with_context(|| format!("Quiz no longer contains question {:?}", key))
+        };
+
+        let remaining_questions = snapshot
+            .remaining_questions
+            .iter()
+            .map(&find_question)
+            .collect::<Result<HashSet<Question>>>()?;
+        let max_question_score_value = definition
+            .get_questions()
+            .iter()
+            .map(|q| q.score_value)
+            .max()
+            .unwrap_or(0);
+
+        let resumed_phase = match snapshot.phase {
+            PhaseSnapshot::Startup | PhaseSnapshot::Vote => None,
+            PhaseSnapshot::Cooldown => Some(Phase::Cooldown(CooldownState::new(
+                settings.cooldown_duration,
+            ))),
+            PhaseSnapshot::Wager {
+                question,
+                participants,
+            } => Some(Phase::Wager(WagerState::new(
+                find_question(&question)?,
+                settings.wager_duration,
+                teams.clone(),
+                output.clone(),
+                participants,
+                max_question_score_value,
+            ))),
+            PhaseSnapshot::Question {
+                question,
+                participants,
+                wagers,
+            } => {
+                let mut state = QuestionState::new(
+                    find_question(&question)?,
+                    settings.question_duration,
+                    teams.clone(),
+                    stats.clone(),
+                    output.clone(),
+                    participants,
+                    wagers.map(|w| w.into_iter().collect()),
+                );
+                if settings.steal_duration.is_some() {
+                    state.allow_steal();
+                }
+                state.set_reveal(&settings.reveal);
+                Some(Phase::Question(state))
+            }
+            PhaseSnapshot::Steal {
+                question,
+                stolen_from,
+                participants,
+            } => Some(Phase::Steal(StealState::new(
+                find_question(&question)?,
+                settings.steal_duration.unwrap_or(DEFAULT_STEAL_DURATION),
+                stolen_from,
+                participants,
+                teams.clone(),
+                stats.clone(),
+                output.clone(),
+            ))),
+            PhaseSnapshot::Tiebreaker {
+                question,
+                participants,
+            } => Some(Phase::Tiebreaker(TiebreakerState::new(
+                find_question(&question)?,
+                settings.question_duration,
+                participants,
+                teams.clone(),
+                stats.clone(),
+                output.clone(),
+            ))),
+            PhaseSnapshot::Results => return Err(anyhow!("The interrupted quiz was already over")),
+        };
+        let final_results = match snapshot.final_results {
+            Some((question, results)) => Some((find_question(&question)?, results)),
+            None => None,
+        };
+
+        let mut questions: Vec<Question> = remaining_questions.iter().cloned().collect();
+        match &resumed_phase {
+            Some(Phase::Wager(s)) => questions.push(s.question.clone()),
+            Some(Phase::Question(s)) => questions.push(s.get_question().clone()),
+            Some(Phase::Steal(s)) => questions.push(s.question.clone()),
+            Some(Phase::Tiebreaker(s)) => questions.push(s.question.clone()),
+            _ => (),
+        }
+        let startup_state = StartupState::new(
+            Duration::default(),
+            questions,
+            definition.get_metadata().cloned(),
+            output.clone(),
+        )?;
+
+        Ok(Quiz {
+            abort: false,
+            remaining_questions,
+            current_phase: Phase::Startup(startup_state),
+            previous_question: None,
+            previous_steal: None,
+            max_question_score_value,
+            initiative: snapshot.initiative,
+            final_results,
+            contributions: snapshot.contributions.into_iter().collect(),
+            quiz_path: definition.get_path().to_path_buf(),
+            settings_overrides: snapshot.settings_overrides,
+            snapshot_path,
+            resumed_phase,
+            output,
+            settings,
+            teams,
+            stats,
+        })
+    }
+
+    pub fn start(&mut self) {
+        if let Phase::Startup(startup_state) = &self.current_phase {
+            let startup_state = startup_state.clone();
+            self.set_current_phase(Phase::Startup(startup_state));
+        }
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.quiz_path
+    }
+
+    fn make_settings(definition: &QuizDefinition, overrides: &SettingsOverrides) -> Settings {
+        let mut settings = Settings::default();
+        settings.apply(definition.get_settings());
+        settings.apply(overrides);
+        settings
     }
 
     pub fn is_over(&self) -> bool {
@@ -88,9 +280,80 @@ impl<O: GameOutput + Clone> Quiz<O> {
     fn set_current_phase(&mut self, phase: Phase<O>) {
         let state = self.current_phase.get_state();
         state.on_end();
-        self.current_phase = phase;
+        // Songs played and guesses made during the phase are written to disk once it is over
+        preload::cache::save_plays();
+        self.stats.write().save();
+        let previous_phase = std::mem::replace(&mut self.current_phase, phase);
+        match (previous_phase, &self.current_phase) {
+            (_, Phase::Wager(_)) | (_, Phase::Question(_)) => {
+                self.previous_question = None;
+                self.previous_steal = None;
+            }
+            (Phase::Question(s), _) => self.previous_question = Some(s),
+            (Phase::Steal(s), _) => self.previous_steal = Some(s),
+            _ => (),
+        }
         let state = self.current_phase.get_state();
         state.on_begin();
+        self.save_snapshot();
+    }
+
+    fn save_snapshot(&self) {
+        let snapshot_path = match &self.snapshot_path {
+            Some(p) => p,
+            None => return,
+        };
+        if self.resumed_phase.is_some() {
+            // Keep the snapshot of the interrupted phase until we are back to it
+            return;
+        }
+        let phase = match &self.current_phase {
+            Phase::Startup(_) => PhaseSnapshot::Startup,
+            Phase::Cooldown(_) => PhaseSnapshot::Cooldown,
+            Phase::Vote(_) => PhaseSnapshot::Vote,
+            Phase::Wager(s) => PhaseSnapshot::Wager {
+                question: (&s.question).into(),
+                participants: s.participants.clone(),
+            },
+            Phase::Question(s) => PhaseSnapshot::Question {
+                question: s.get_question().into(),
+                participants: s.get_participants().clone(),
+                wagers: s
+                    .get_wagers()
+                    .map(|w| w.iter().map(|(t, a)| (t.clone(), *a)).collect()),
+            },
+            Phase::Steal(s) => PhaseSnapshot::Steal {
+                question: (&s.question).into(),
+                stolen_from: s.stolen_from.clone(),
+                participants: s.participants.clone(),
+            },
+            Phase::Tiebreaker(s) => PhaseSnapshot::Tiebreaker {
+                question: (&s.question).into(),
+                participants: s.participants.clone(),
+            },
+            Phase::Results(_) => {
+                if let Err(e) = Snapshot::delete(snapshot_path) {
+                    eprintln!("Could not delete quiz snapshot: {:#}", e);
+                }
+                return;
+            }
+        };
+        let snapshot = Snapshot {
+            quiz_path: self.quiz_path.clone(),
+            settings_overrides: self.settings_overrides.clone(),
+            teams: self.teams.read().clone(),
+            initiative: self.initiative.clone(),
+            contributions: self.contributions.iter().map(|(p, s)| (*p, *s)).collect(),
+            remaining_questions: self.remaining_questions.iter().map(|q| q.into()).collect(),
+            final_results: self
+                .final_results
+                .as_ref()
+                .map(|(q, results)| (q.into(), results.clone())),
+            phase,
+        };
+        if let Err(e) = snapshot.write(snapshot_path) {
+            eprintln!("Could not save quiz snapshot: {:#}", e);
+        }
     }
 
     pub fn tick(&mut self, dt: Duration) {
@@ -101,16 +364,73 @@ impl<O: GameOutput + Clone> Quiz<O> {
         }
     }
 
-    pub fn guess(&mut self, team_id: &TeamId, guess: &str) -> Result<()> {
-        match &mut self.current_phase {
-            Phase::Question(question_state) => {
-                let guess_result = question_state.guess(team_id, guess)?;
-                if guess_result.is_first_correct {
-                    self.initiative = Some(team_id.clone());
+    pub fn guess(&mut self, team_id: &TeamId, player: UserId, guess: &str) -> Result<()> {
+        let guess_result = match &mut self.current_phase {
+            Phase::Question(question_state) => question_state.guess(team_id, player, guess)?,
+            Phase::Steal(steal_state) => steal_state.guess(team_id, player, guess)?,
+            Phase::Tiebreaker(tiebreaker_state) => {
+                tiebreaker_state.guess(team_id, player, guess)?
+            }
+            _ => return Err(anyhow!("There is no active question")),
+        };
+        *self.contributions.entry(guess_result.player).or_default() += guess_result.score_delta;
+        if guess_result.is_first_correct {
+            self.initiative = Some(team_id.clone());
+        }
+        Ok(())
+    }
+
+    pub fn rejudge(&mut self, team_id: &TeamId, is_correct: bool) -> Result<()> {
+        // The tiebreaker decides the winner on the spot, so its guesses cannot be overruled
+        if let Phase::Tiebreaker(_) = self.current_phase {
+            return Err(anyhow!("Guesses cannot be rejudged during a tiebreaker"));
+        }
+        let steal_state = match &mut self.current_phase {
+            Phase::Steal(s) => Some(s),
+            _ => self.previous_steal.as_mut(),
+        };
+        let (guess_result, correction) = match steal_state {
+            Some(s) if s.has_guessed(team_id) => s.rejudge(team_id, is_correct)?,
+            _ => {
+                let question_state = match &mut self.current_phase {
+                    Phase::Question(s) => s,
+                    _ => self
+                        .previous_question
+                        .as_mut()
+                        .context("There is no question to judge")?,
+                };
+                let rejudged = question_state.rejudge(team_id, is_correct)?;
+                if let Some((question, results)) = &mut self.final_results {
+                    if question == question_state.get_question() {
+                        *results = question_state.get_final_results();
+                    }
                 }
-                Ok(())
+                rejudged
+            }
+        };
+        // The steal round is over as soon as the question was answered correctly
+        if let Phase::Steal(s) = &mut self.current_phase {
+            if is_correct {
+                s.close();
             }
-            _ => Err(anyhow!("There is no active question")),
+        }
+        *self.contributions.entry(guess_result.player).or_default() += correction;
+        if guess_result.is_first_correct {
+            self.initiative = Some(team_id.clone());
+        } else if !is_correct && self.initiative.as_ref() == Some(team_id) {
+            self.initiative = None;
+        }
+        Ok(())
+    }
+
+    pub fn forget_contribution(&mut self, player: UserId, delta: i32) {
+        *self.contributions.entry(player).or_default() -= delta;
+    }
+
+    // Lets a team that was not around when the question began answer it, unless it is a challenge
+    pub fn admit(&mut self, team_id: &TeamId) {
+        if let Phase::Question(question_state) = &mut self.current_phase {
+            question_state.admit(team_id);
         }
     }
 
@@ -126,6 +446,50 @@ impl<O: GameOutput + Clone> Quiz<O> {
 
     pub fn abort(&mut self) {
         self.abort = true;
+        if let Some(snapshot_path) = &self.snapshot_path {
+            if let Err(e) = Snapshot::delete(snapshot_path) {
+                eprintln!("Could not delete quiz snapshot: {:#}", e);
+            }
+        }
+    }
+
+    pub fn continue_without_failed_questions(&mut self) -> Result<()> {
+        let failed_questions = match &self.current_phase {
+            Phase::Startup(s) if s.preload_failed() => s.get_failed_questions(),
+            _ => return Err(anyhow!("No song failed to download")),
+        };
+        for (question, _reason) in &failed_questions {
+            self.remaining_questions.remove(question);
+        }
+        let resumed_question_failed = match &self.resumed_phase {
+            Some(Phase::Wager(s)) => failed_questions.iter().any(|(q, _)| *q == s.question),
+            Some(Phase::Question(s)) => failed_questions.iter().any(|(q, _)| q == s.get_question()),
+            _ => false,
+        };
+        if resumed_question_failed {
+            self.resumed_phase = None;
+        }
+        // A tiebreaker without its song moves on to the next tiebreaker question, if any
+        if let Some(Phase::Tiebreaker(s)) = &self.resumed_phase {
+            if failed_questions.iter().any(|(q, _)| *q == s.question) {
+                let state = CooldownState::new(self.settings.cooldown_duration);
+                self.resumed_phase = Some(Phase::Cooldown(state));
+            }
+        }
+        if self.remaining_questions.is_empty() && self.resumed_phase.is_none() {
+            return Err(anyhow!(
+                "Every song failed to download, use `!end` to stop the quiz"
+            ));
+        }
+        self.begin_after_startup();
+        Ok(())
+    }
+
+    fn begin_after_startup(&mut self) {
+        match self.resumed_phase.take() {
+            Some(phase) => self.set_current_phase(phase),
+            None => self.begin_vote(),
+        }
     }
 
     pub fn skip_phase(&mut self) {
@@ -135,43 +499,67 @@ impl<O: GameOutput + Clone> Quiz<O> {
     fn advance(&mut self) {
         match &self.current_phase {
             Phase::Startup(s) => {
+                // Songs which failed to preload are left for the quizmaster to deal with
                 if s.preload_succeeded() {
-                    self.begin_vote();
-                } else {
-                    self.output
-                        .say(&Recipient::AllTeams, &Message::PreloadFailed);
-                    self.abort = true;
+                    self.begin_after_startup();
                 }
             }
             Phase::Vote(_s) => {
                 self.initiate_question();
             }
             Phase::Wager(s) => {
-                let state = QuestionState::new(
+                let mut state = QuestionState::new(
                     s.question.clone(),
                     self.settings.question_duration,
                     self.teams.clone(),
+                    self.stats.clone(),
                     self.output.clone(),
                     s.participants.clone(),
-                    Some(s.wagers.clone()),
+                    Some(s.get_wagers()),
                 );
+                state.set_reveal(&self.settings.reveal);
                 self.set_current_phase(Phase::Question(state));
             }
-            Phase::Question(_s) => {
+            Phase::Question(s) if s.get_question().final_round => {
+                // Teams which did not answer only lose their wager once the question is over
+                self.final_results = Some((s.get_question().clone(), s.get_final_results()));
+                let state = CooldownState::new(self.settings.cooldown_duration);
+                self.set_current_phase(Phase::Cooldown(state));
+            }
+            Phase::Question(s) => match s.get_stolen_from() {
+                Some(team_id) => {
+                    let state = StealState::new(
+                        s.get_question().clone(),
+                        self.settings
+                            .steal_duration
+                            .unwrap_or(DEFAULT_STEAL_DURATION),
+                        team_id.clone(),
+                        s.get_remaining_participants(),
+                        self.teams.clone(),
+                        self.stats.clone(),
+                        self.output.clone(),
+                    );
+                    self.set_current_phase(Phase::Steal(state));
+                }
+                None => {
+                    let state = CooldownState::new(self.settings.cooldown_duration);
+                    self.set_current_phase(Phase::Cooldown(state));
+                }
+            },
+            Phase::Steal(_s) => {
                 let state = CooldownState::new(self.settings.cooldown_duration);
                 self.set_current_phase(Phase::Cooldown(state));
             }
+            Phase::Tiebreaker(_s) => self.finish(),
             Phase::Cooldown(_s) => {
                 let remaining_categories: HashSet<&str> = self
                     .remaining_questions
                     .iter()
+                    .filter(|q| !q.is_reserved())
                     .map(|q| q.category.as_str())
                     .collect();
                 match remaining_categories.len() {
-                    0 => self.set_current_phase(Phase::Results(ResultsState::new(
-                        self.teams.clone(),
-                        self.output.clone(),
-                    ))),
+                    0 => self.end_regular_rounds(),
                     1 => self.initiate_question(),
                     _ => self.begin_vote(),
                 }
@@ -202,28 +590,131 @@ impl<O: GameOutput + Clone> Quiz<O> {
                 self.set_current_phase(Phase::Wager(state));
             } else {
                 let participants = self.teams.read().iter().map(|t| t.id.clone()).collect();
-                let state = QuestionState::new(
+                let mut state = QuestionState::new(
                     question,
                     self.settings.question_duration,
                     self.teams.clone(),
+                    self.stats.clone(),
                     self.output.clone(),
                     participants,
                     None,
                 );
+                if self.settings.steal_duration.is_some() {
+                    state.allow_steal();
+                }
+                state.set_reveal(&self.settings.reveal);
                 self.set_current_phase(Phase::Question(state));
             }
         } else {
-            self.set_current_phase(Phase::Results(ResultsState::new(
-                self.teams.clone(),
-                self.output.clone(),
-            )));
+            self.end_regular_rounds();
+        }
+    }
+
+    // Once every category is done, teams with points wager blind on the final question
+    fn end_regular_rounds(&mut self) {
+        let final_question = self
+            .remaining_questions
+            .iter()
+            .find(|q| q.final_round)
+            .cloned();
+        let participants: HashSet<TeamId> = self
+            .teams
+            .read()
+            .iter()
+            .filter(|t| t.score > 0)
+            .map(|t| t.id.clone())
+            .collect();
+        match final_question {
+            Some(question) if !participants.is_empty() => {
+                self.remaining_questions.remove(&question);
+                let state = WagerState::new(
+                    question,
+                    self.settings.wager_duration,
+                    self.teams.clone(),
+                    self.output.clone(),
+                    participants,
+                    self.max_question_score_value,
+                );
+                self.set_current_phase(Phase::Wager(state));
+            }
+            _ => self.finish(),
+        }
+    }
+
+    // Tied winners play sudden death on the tiebreaker questions until one of them wins
+    fn finish(&mut self) {
+        let final_results = self.final_results.take();
+        let tied_teams = self.get_tied_teams();
+        let tiebreaker = self
+            .remaining_questions
+            .iter()
+            .filter(|q| q.tiebreaker)
+            .min_by_key(|q| q.score_value)
+            .cloned();
+        match tiebreaker {
+            Some(question) if tied_teams.len() > 1 => {
+                if let Some((question, results)) = final_results {
+                    self.output.say(
+                        &Recipient::AllTeams,
+                        &Message::FinalRoundResults(question, results),
+                    );
+                }
+                self.remaining_questions.remove(&question);
+                let state = TiebreakerState::new(
+                    question,
+                    self.settings.question_duration,
+                    tied_teams,
+                    self.teams.clone(),
+                    self.stats.clone(),
+                    self.output.clone(),
+                );
+                self.set_current_phase(Phase::Tiebreaker(state));
+            }
+            _ => {
+                let mut state = ResultsState::new(
+                    self.teams.clone(),
+                    self.stats.clone(),
+                    self.get_contributions(),
+                    self.output.clone(),
+                );
+                if let Some((question, results)) = final_results {
+                    state.set_final_results(question, results);
+                }
+                self.set_current_phase(Phase::Results(state));
+            }
         }
     }
 
+    fn get_tied_teams(&self) -> HashSet<TeamId> {
+        let teams = self.teams.read();
+        let top_score = teams.iter().map(|t| t.score).max();
+        teams
+            .iter()
+            .filter(|t| Some(t.score) == top_score)
+            .map(|t| t.id.clone())
+            .collect()
+    }
+
+    // Points earned by each player who guessed, best first
+    fn get_contributions(&self) -> Vec<(UserId, i32)> {
+        let mut contributions: Vec<(UserId, i32)> =
+            self.contributions.iter().map(|(p, s)| (*p, *s)).collect();
+        contributions.sort_by(|(a_player, a_score), (b_player, b_score)| {
+            b_score.cmp(a_score).then(a_player.cmp(b_player))
+        });
+        contributions
+    }
+
     fn begin_vote(&mut self) {
+        let regular_questions = self
+            .remaining_questions
+            .iter()
+            .filter(|q| !q.is_reserved())
+            .cloned()
+            .collect();
         let state = VoteState::new(
             self.settings.vote_duration,
-            &self.remaining_questions,
+            &regular_questions,
             self.initiative.clone(),
             self.teams.clone(),
             self.output.clone(),
@@ -235,12 +726,17 @@ impl<O: GameOutput + Clone> Quiz<O> {
     fn select_question(&mut self) -> Option<Question> {
         if let Phase::Vote(vote_state) = &self.current_phase {
             if let Ok(question) = vote_state.compute_vote_result() {
+                self.output.record(&Event::VoteResult {
+                    category: question.category.clone(),
+                    score_value: question.score_value,
+                });
                 return self.remaining_questions.take(&question);
             }
         }
         let question = self
             .remaining_questions
             .iter()
+            .filter(|q| !q.is_reserved())
             .min_by_key(|q| q.score_value)
             .cloned();
         if let Some(question) = question {
@@ -1,7 +1,19 @@
+use anyhow::*;
+use serenity::model::id::UserId;
+use std::cmp::Reverse;
+
+use crate::game::quiz::definition::Question;
+use crate::game::stats::StatsHandle;
+use crate::game::team::{GuessRecord, ScoreReason};
+use crate::game::{TeamId, TeamsHandle};
+use crate::output::{Event, GameOutput, Message, Recipient};
+
 mod cooldown;
 mod question;
 mod results;
 mod startup;
+mod steal;
+mod tiebreaker;
 mod vote;
 mod wager;
 
@@ -9,5 +21,134 @@ pub use self::cooldown::*;
 pub use self::question::*;
 pub use self::results::*;
 pub use self::startup::*;
+pub use self::steal::*;
+pub use self::tiebreaker::*;
 pub use self::vote::*;
 pub use self::wager::*;
+
+#[derive(Clone, Debug)]
+pub struct GuessResult {
+    pub player: UserId,
+    pub guess: String,
+    pub score_delta: i32,
+    pub is_correct: bool,
+    pub is_first_correct: bool,
+    // Progressive reveal stage during which the guess was made
+    pub reveal_stage: usize,
+}
+
+pub fn record_guess<O: GameOutput>(output: &O, team_id: &TeamId, guess_result: &GuessResult) {
+    output.record(&Event::Guess {
+        team_id: team_id.clone(),
+        player: guess_result.player,
+        guess: guess_result.guess.clone(),
+        is_correct: guess_result.is_correct,
+        score_delta: guess_result.score_delta,
+    });
+}
+
+pub fn update_score<O: GameOutput>(
+    teams: &TeamsHandle,
+    output: &O,
+    question: &Question,
+    team_id: &TeamId,
+    delta: i32,
+    reason: ScoreReason,
+    guess: Option<GuessRecord>,
+) -> Result<()> {
+    let score = {
+        let mut teams = teams.write();
+        let team = teams
+            .iter_mut()
+            .find(|t| t.id == *team_id)
+            .context("Team not found")?;
+        team.update_score_for_guess(delta, reason, Some(question.answer.clone()), guess);
+        team.score
+    };
+    output.record(&Event::ScoreChange {
+        team_id: team_id.clone(),
+        delta,
+        score,
+    });
+    Ok(())
+}
+
+fn make_guess_record(question: &Question, guess_result: &GuessResult) -> GuessRecord {
+    GuessRecord {
+        player: guess_result.player,
+        category: question.category.clone(),
+        is_correct: guess_result.is_correct,
+    }
+}
+
+// Everything a judged guess changes, whichever phase it was made in: the game record,
+// the team's score and history, and the player's stats.
+// Guesses worth nothing, like misses in a tiebreaker, leave the score history alone.
+pub fn score_guess<O: GameOutput>(
+    teams: &TeamsHandle,
+    stats: &StatsHandle,
+    output: &O,
+    question: &Question,
+    team_id: &TeamId,
+    guess_result: &GuessResult,
+) -> Result<()> {
+    record_guess(output, team_id, guess_result);
+    if guess_result.score_delta != 0 {
+        let reason = if guess_result.is_correct {
+            ScoreReason::CorrectGuess
+        } else {
+            ScoreReason::IncorrectGuess
+        };
+        update_score(
+            teams,
+            output,
+            question,
+            team_id,
+            guess_result.score_delta,
+            reason,
+            Some(make_guess_record(question, guess_result)),
+        )?;
+    }
+    stats.write().record_guess(
+        guess_result.player,
+        &question.category,
+        guess_result.is_correct,
+        guess_result.score_delta,
+    );
+    Ok(())
+}
+
+// Same as `score_guess` for a guess whose judgement was overruled, where only the correction is applied
+pub fn rescore_guess<O: GameOutput>(
+    teams: &TeamsHandle,
+    stats: &StatsHandle,
+    output: &O,
+    question: &Question,
+    team_id: &TeamId,
+    guess_result: &GuessResult,
+    correction: i32,
+) -> Result<()> {
+    update_score(
+        teams,
+        output,
+        question,
+        team_id,
+        correction,
+        ScoreReason::Rejudged,
+        Some(make_guess_record(question, guess_result)),
+    )?;
+    stats.write().rejudge_guess(
+        guess_result.player,
+        &question.category,
+        guess_result.is_correct,
+        correction,
+    );
+    Ok(())
+}
+
+pub fn print_scores<O: GameOutput>(teams: &TeamsHandle, output: &O) {
+    let mut teams = teams.read().clone();
+    teams.sort_by_key(|t| Reverse(t.score));
+    let teams = teams.iter().map(|t| (t.id.clone(), t.score)).collect();
+    output.say(&Recipient::AllTeams, &Message::ScoresRecap(teams));
+}
@@ -1,12 +1,18 @@
 use anyhow::*;
-use std::cmp::Reverse;
+use serenity::model::id::UserId;
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::time::Duration;
 
 use crate::game::quiz::assets::*;
-use crate::game::quiz::definition::Question;
+use crate::game::quiz::definition::{GuessJudgement, Question};
+use crate::game::quiz::phase::{
+    print_scores, record_guess, rescore_guess, score_guess, update_score, GuessResult,
+};
+use crate::game::quiz::settings::Reveal;
 use crate::game::quiz::State;
+use crate::game::stats::StatsHandle;
+use crate::game::team::ScoreReason;
 use crate::game::{TeamId, TeamsHandle};
 use crate::output::{AudioHandle, GameOutput, Message, Recipient};
 use crate::preload;
@@ -14,22 +20,29 @@ use crate::preload;
 #[cfg(test)]
 mod tests;
 
-#[derive(Clone, Debug)]
-pub struct GuessResult {
-    pub guess: String,
-    pub score_delta: i32,
-    pub is_correct: bool,
-    pub is_first_correct: bool,
-}
+// Silence between the clips of a progressive reveal question
+const REVEAL_PAUSE: Duration = Duration::from_secs(2);
+
+// What each team answered to the final question, if anything, and the points it won or lost
+pub type FinalResults = Vec<(TeamId, Option<String>, i32)>;
 
 pub struct QuestionState<O: GameOutput> {
     question: Question,
     time_elapsed: Duration,
     default_time_limit: Duration,
     guesses: HashMap<TeamId, GuessResult>,
+    // Team whose guess was judged first, which is the only one to earn or lose full points
+    first_guess: Option<TeamId>,
+    close_guesses: HashSet<TeamId>,
     teams: TeamsHandle,
+    stats: StatsHandle,
     participants: HashSet<TeamId>,
     wagers: Option<HashMap<TeamId, u32>>,
+    steal_allowed: bool,
+    // Team whose wrong guess interrupted the question for a steal round
+    stolen_from: Option<TeamId>,
+    reveal: Option<Reveal>,
+    song_started_at: Option<Duration>,
     countdown_audio: Option<O::Audio>,
     song_audio: Option<O::Audio>,
     output: O,
@@ -40,25 +53,110 @@ impl<O: GameOutput> QuestionState<O> {
         question: Question,
         duration: Duration,
         teams: TeamsHandle,
+        stats: StatsHandle,
         output: O,
         participants: HashSet<TeamId>,
         wagers: Option<HashMap<TeamId, u32>>,
     ) -> Self {
         QuestionState {
-            question,
             time_elapsed: Duration::default(),
             default_time_limit: duration,
             guesses: HashMap::new(),
+            first_guess: None,
+            close_guesses: HashSet::new(),
             teams,
+            stats,
             participants,
+            steal_allowed: question.steal && wagers.is_none(),
+            stolen_from: None,
+            reveal: None,
+            song_started_at: None,
             wagers,
             countdown_audio: None,
             song_audio: None,
+            question,
             output,
         }
     }
 
-    pub fn guess(&mut self, team_id: &TeamId, guess: &str) -> Result<GuessResult> {
+    pub fn get_question(&self) -> &Question {
+        &self.question
+    }
+
+    pub fn get_participants(&self) -> &HashSet<TeamId> {
+        &self.participants
+    }
+
+    // Challenge questions only accept the teams that wagered
+    pub fn admit(&mut self, team_id: &TeamId) {
+        if self.wagers.is_none() {
+            self.participants.insert(team_id.clone());
+        }
+    }
+
+    // Challenge questions cannot be stolen since only the teams that wagered answer them
+    pub fn allow_steal(&mut self) {
+        self.steal_allowed = self.wagers.is_none();
+    }
+
+    // Only progressive reveal questions are played in stages
+    pub fn set_reveal(&mut self, reveal: &Reveal) {
+        if self.question.reveal {
+            self.reveal = Some(reveal.clone());
+        }
+    }
+
+    fn get_reveal_stage(&self) -> usize {
+        match (&self.reveal, self.song_started_at) {
+            (Some(reveal), Some(started_at)) => {
+                get_reveal_position(reveal, self.time_elapsed.saturating_sub(started_at)).0
+            }
+            _ => 0,
+        }
+    }
+
+    pub fn get_stolen_from(&self) -> Option<&TeamId> {
+        self.stolen_from.as_ref()
+    }
+
+    // Participants who have not made a guess yet
+    pub fn get_remaining_participants(&self) -> HashSet<TeamId> {
+        self.participants
+            .iter()
+            .filter(|t| !self.guesses.contains_key(*t))
+            .cloned()
+            .collect()
+    }
+
+    pub fn get_wagers(&self) -> Option<&HashMap<TeamId, u32>> {
+        self.wagers.as_ref()
+    }
+
+    pub fn get_final_results(&self) -> FinalResults {
+        self.teams
+            .read()
+            .iter()
+            .filter(|t| self.participants.contains(&t.id))
+            .map(|t| match self.guesses.get(&t.id) {
+                Some(guess) => (t.id.clone(), Some(guess.guess.clone()), guess.score_delta),
+                None => (t.id.clone(), None, -self.compute_score_value(&t.id)),
+            })
+            .collect()
+    }
+
+    fn update_score(&self, team_id: &TeamId, delta: i32, reason: ScoreReason) -> Result<()> {
+        update_score(
+            &self.teams,
+            &self.output,
+            &self.question,
+            team_id,
+            delta,
+            reason,
+            None,
+        )
+    }
+
+    pub fn guess(&mut self, team_id: &TeamId, player: UserId, guess: &str) -> Result<GuessResult> {
         if self.guesses.contains_key(team_id) {
             return Err(anyhow!("Team already made a guess"));
         }
@@ -67,23 +165,67 @@ impl<O: GameOutput> QuestionState<O> {
             return Err(anyhow!("Your team is not allowed to answer this question"));
         }
 
-        let is_correct = self.question.is_guess_correct(guess);
-        let score_delta = self.compute_score_delta(team_id, is_correct);
+        let judgement = self.question.judge_guess(guess);
+
+        // Close guesses get one more chance instead of a penalty
+        if judgement == GuessJudgement::Close && self.close_guesses.insert(team_id.clone()) {
+            self.output.say(
+                &Recipient::Team(team_id.clone()),
+                &Message::GuessClose(guess.into()),
+            );
+            let guess_result = GuessResult {
+                player,
+                guess: guess.into(),
+                is_correct: false,
+                score_delta: 0,
+                is_first_correct: false,
+                reveal_stage: self.get_reveal_stage(),
+            };
+            record_guess(&self.output, team_id, &guess_result);
+            return Ok(guess_result);
+        }
+
+        let is_correct = judgement == GuessJudgement::Correct;
+        let reveal_stage = self.get_reveal_stage();
+        let score_delta = self.compute_score_delta(team_id, is_correct, reveal_stage);
         let is_first_correct = is_correct && !self.was_correctly_guessed();
         let guess_result = GuessResult {
+            player,
             guess: guess.into(),
             is_correct,
             score_delta,
             is_first_correct,
+            reveal_stage,
         };
         self.guesses.insert(team_id.clone(), guess_result.clone());
+        self.first_guess.get_or_insert_with(|| team_id.clone());
+        score_guess(
+            &self.teams,
+            &self.stats,
+            &self.output,
+            &self.question,
+            team_id,
+            &guess_result,
+        )?;
 
-        self.teams
-            .write()
-            .iter_mut()
-            .find(|t| t.id == *team_id)
-            .context("Team not found")?
-            .update_score(guess_result.score_delta);
+        // The first wrong guess opens a steal round for the teams that have not guessed yet
+        if self.steal_allowed
+            && !is_correct
+            && self.stolen_from.is_none()
+            && !self.was_correctly_guessed()
+            && !self.did_every_team_submit_a_guess()
+        {
+            self.stolen_from = Some(team_id.clone());
+        }
+
+        // Answers to the final question are only revealed with the results
+        if self.question.final_round {
+            self.output.say(
+                &Recipient::Team(team_id.clone()),
+                &Message::FinalGuessRecorded,
+            );
+            return Ok(guess_result);
+        }
 
         if guess_result.is_correct {
             self.output.play_file_audio(Path::new(SFX_CORRECT)).ok();
@@ -110,6 +252,56 @@ impl<O: GameOutput> QuestionState<O> {
         Ok(guess_result)
     }
 
+    // Overrules the judgement of a team's guess, returning the corrected guess and the score correction
+    pub fn rejudge(&mut self, team_id: &TeamId, is_correct: bool) -> Result<(GuessResult, i32)> {
+        let previous = self
+            .guesses
+            .get(team_id)
+            .cloned()
+            .context("This team did not make a guess")?;
+        if previous.is_correct == is_correct {
+            return Err(anyhow!(
+                "This guess was already {}",
+                if is_correct { "accepted" } else { "rejected" }
+            ));
+        }
+
+        let score_delta = self.compute_score_delta(team_id, is_correct, previous.reveal_stage);
+        let is_first_correct = is_correct && !self.was_correctly_guessed();
+        let guess_result = GuessResult {
+            is_correct,
+            score_delta,
+            is_first_correct,
+            ..previous.clone()
+        };
+        let correction = score_delta - previous.score_delta;
+        self.guesses.insert(team_id.clone(), guess_result.clone());
+        rescore_guess(
+            &self.teams,
+            &self.stats,
+            &self.output,
+            &self.question,
+            team_id,
+            &guess_result,
+            correction,
+        )?;
+
+        let message = if is_correct {
+            Message::GuessAccepted(team_id.clone(), correction)
+        } else {
+            Message::GuessRejected(team_id.clone(), correction)
+        };
+        // Answers to the final question are only revealed with the results
+        let recipient = if self.question.final_round {
+            Recipient::Quizmaster
+        } else {
+            Recipient::AllTeams
+        };
+        self.output.say(&recipient, &message);
+
+        Ok((guess_result, correction))
+    }
+
     fn was_correctly_guessed(&self) -> bool {
         self.guesses.iter().any(|(_t, g)| g.is_correct)
     }
@@ -124,7 +316,8 @@ impl<O: GameOutput> QuestionState<O> {
             .as_ref()
             .and_then(|w| w.get(team_id).copied())
             .unwrap_or(self.question.score_value) as i32;
-        let is_first_guess = self.guesses.is_empty();
+        let is_first_guess =
+            self.first_guess.is_none() || self.first_guess.as_ref() == Some(team_id);
         if is_first_guess || self.wagers.is_some() {
             score_value
         } else {
@@ -132,8 +325,17 @@ impl<O: GameOutput> QuestionState<O> {
         }
     }
 
-    fn compute_score_delta(&self, team_id: &TeamId, correct: bool) -> i32 {
+    // Progressive reveal questions are worth less the longer the song has played
+    fn compute_guess_value(&self, team_id: &TeamId, reveal_stage: usize) -> i32 {
         let score_value = self.compute_score_value(team_id);
+        match &self.reveal {
+            Some(reveal) => score_value * reveal.get_multiplier(reveal_stage) as i32 / 100,
+            None => score_value,
+        }
+    }
+
+    fn compute_score_delta(&self, team_id: &TeamId, correct: bool, reveal_stage: usize) -> i32 {
+        let score_value = self.compute_guess_value(team_id, reveal_stage);
         let correctness_multiplier = if correct { 1 } else { -1 };
         score_value * correctness_multiplier
     }
@@ -151,14 +353,6 @@ impl<O: GameOutput> QuestionState<O> {
             .say(&Recipient::AllTeams, &Message::GuessesReveal(guesses));
     }
 
-    fn print_scores(&self) {
-        let mut teams = self.teams.read().clone();
-        teams.sort_by_key(|t| Reverse(t.score));
-        let teams = teams.iter().map(|t| (t.id.clone(), t.score)).collect();
-        self.output
-            .say(&Recipient::AllTeams, &Message::ScoresRecap(teams));
-    }
-
     fn print_time_remaining(&self, before: &Option<Duration>, after: &Option<Duration>) {
         match (before, after) {
             (Some(before), Some(after)) => {
@@ -182,9 +376,61 @@ impl<O: GameOutput> QuestionState<O> {
         };
     }
 
+    // The clips of progressive reveal questions do not count against the time to guess
     fn get_time_limit(&self) -> Duration {
-        self.question.duration.unwrap_or(self.default_time_limit)
+        let time_limit = self.question.duration.unwrap_or(self.default_time_limit);
+        match &self.reveal {
+            Some(reveal) => reveal
+                .stages
+                .iter()
+                .fold(time_limit, |total, stage| total + *stage + REVEAL_PAUSE),
+            None => time_limit,
+        }
     }
+
+    fn play_song(&mut self) {
+        self.song_audio = play_song(&self.output, &self.question);
+    }
+
+    fn announce_reveal_stage(&self, stage: usize) {
+        if let Some(reveal) = &self.reveal {
+            let worth = self.question.score_value * reveal.get_multiplier(stage) / 100;
+            self.output.say(
+                &Recipient::AllTeams,
+                &Message::RevealStage(reveal.stages.get(stage).copied(), worth),
+            );
+        }
+    }
+}
+
+// Plays a question's song from the cache or a local file, or streams it from Youtube
+pub fn play_song<O: GameOutput>(output: &O, question: &Question) -> Option<O::Audio> {
+    if let Some(cache_entry) = preload::retrieve_song(&question.get_song()) {
+        output.play_file_audio(&cache_entry.path).ok()
+    } else if preload::is_local(&question.url) {
+        let path = preload::local_url_to_path(&question.url);
+        output.play_file_audio(&path).ok()
+    } else if preload::is_offline() {
+        eprintln!("Not streaming song in offline mode: {}", question.url);
+        None
+    } else {
+        output.play_youtube_audio(question.url.clone()).ok()
+    }
+}
+
+// Stage of a progressive reveal reached after `elapsed` since the first clip began, and whether a clip is playing
+fn get_reveal_position(reveal: &Reveal, mut elapsed: Duration) -> (usize, bool) {
+    for (stage, length) in reveal.stages.iter().enumerate() {
+        if elapsed < *length {
+            return (stage, true);
+        }
+        elapsed -= *length;
+        if elapsed < REVEAL_PAUSE {
+            return (stage, false);
+        }
+        elapsed -= REVEAL_PAUSE;
+    }
+    (reveal.stages.len(), true)
 }
 
 impl<O: GameOutput> State for QuestionState<O> {
@@ -204,13 +450,28 @@ impl<O: GameOutput> State for QuestionState<O> {
             (None, None) => true,
         };
         if should_start_song {
-            if let Some(cache_entry) = preload::retrieve_song(&self.question.url) {
-                self.song_audio = self.output.play_file_audio(&cache_entry.path).ok();
-            } else {
-                self.song_audio = self
-                    .output
-                    .play_youtube_audio(self.question.url.clone())
-                    .ok();
+            if self.song_started_at.is_none() {
+                self.song_started_at = Some(self.time_elapsed);
+                self.announce_reveal_stage(0);
+            }
+            self.play_song();
+        }
+
+        // Each clip of a progressive reveal starts the song over
+        let reveal_positions = match (&self.reveal, self.song_started_at) {
+            (Some(reveal), Some(started_at)) => Some((
+                get_reveal_position(reveal, (self.time_elapsed - dt).saturating_sub(started_at)),
+                get_reveal_position(reveal, self.time_elapsed.saturating_sub(started_at)),
+            )),
+            _ => None,
+        };
+        if let Some(((previous_stage, was_playing), (stage, is_playing))) = reveal_positions {
+            if was_playing && (!is_playing || stage != previous_stage) {
+                self.output.stop_audio().ok();
+            }
+            if is_playing && (!was_playing || stage != previous_stage) {
+                self.announce_reveal_stage(stage);
+                self.play_song();
             }
         }
     }
@@ -237,6 +498,21 @@ impl<O: GameOutput> State for QuestionState<O> {
     fn on_end(&mut self) {
         self.output.stop_audio().ok();
 
+        // The steal round reveals the answer once it is over
+        if self.stolen_from.is_some() {
+            return;
+        }
+
+        // Teams which did not answer the final question lose their wager without a word
+        if self.question.final_round {
+            for team_id in self.get_remaining_participants() {
+                let score_value = self.compute_score_value(&team_id);
+                self.update_score(&team_id, -score_value, ScoreReason::UnansweredChallenge)
+                    .ok();
+            }
+            return;
+        }
+
         if !self.did_every_team_submit_a_guess() {
             // Reveal answer
             self.output.play_file_audio(Path::new(SFX_TIME)).ok();
@@ -253,10 +529,8 @@ impl<O: GameOutput> State for QuestionState<O> {
                 for team_id in &self.participants {
                     if self.guesses.get(team_id).is_none() {
                         let score_value = self.compute_score_value(team_id);
-                        if let Some(team) = self.teams.write().iter_mut().find(|t| t.id == *team_id)
-                        {
-                            team.update_score(-score_value);
-                        }
+                        self.update_score(team_id, -score_value, ScoreReason::UnansweredChallenge)
+                            .ok();
                         self.output.say(
                             &Recipient::AllTeams,
                             &Message::ChallengeSongTimeUp(team_id.clone(), score_value),
@@ -266,10 +540,10 @@ impl<O: GameOutput> State for QuestionState<O> {
             }
         }
 
-        self.print_scores();
+        print_scores(&self.teams, &self.output);
     }
 
     fn is_over(&self) -> bool {
-        self.time_elapsed >= self.get_time_limit()
+        self.stolen_from.is_some() || self.time_elapsed >= self.get_time_limit()
     }
 }
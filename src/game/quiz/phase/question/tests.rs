@@ -4,9 +4,12 @@ use std::time::Duration;
 
 use super::*;
 use crate::game::quiz::definition::{Question, RawQuestion};
+use crate::game::stats::Stats;
 use crate::game::team::Team;
 use crate::output::mock::MockGameOutput;
 
+const PLAYER: UserId = UserId(1);
+
 struct ContextBuilder {
     question: RawQuestion,
     team_ids: HashMap<String, TeamId>,
@@ -16,15 +19,7 @@ struct ContextBuilder {
 impl ContextBuilder {
     fn new() -> Self {
         ContextBuilder {
-            question: RawQuestion {
-                url: "example url".to_owned(),
-                answer: "example answer".to_owned(),
-                acceptable_answers: None,
-                category: "example category".to_owned(),
-                score_value: 100,
-                challenge: false,
-                duration_seconds: None,
-            },
+            question: RawQuestion::test(),
             team_ids: ["red", "green", "blue"]
                 .iter()
                 .map(|n| (n.to_string(), TeamId::TeamName(n.to_string())))
@@ -65,6 +60,7 @@ impl ContextBuilder {
             question.clone(),
             duration,
             teams.clone(),
+            Arc::new(RwLock::new(Stats::default())),
             output.clone(),
             participants,
             self.wagers,
@@ -131,13 +127,13 @@ fn timeout_announces_scores() {
         .iter_mut()
         .find(|t| t.id == *red)
         .unwrap()
-        .update_score(red_score);
+        .update_score(red_score, ScoreReason::Adjusted, None);
     ctx.teams
         .write()
         .iter_mut()
         .find(|t| t.id == *green)
         .unwrap()
-        .update_score(green_score);
+        .update_score(green_score, ScoreReason::Adjusted, None);
     ctx.state.on_end();
 
     let expected_scores = vec![
@@ -183,27 +179,24 @@ fn can_only_answer_once() {
     let mut ctx = ContextBuilder::new().build();
     let red = ctx.team_ids.get("red").unwrap();
     let blue = ctx.team_ids.get("blue").unwrap();
-    assert!(ctx.state.guess(&blue, "random guess").is_ok());
-    assert!(ctx.state.guess(&red, "random guess").is_ok());
-    assert!(ctx.state.guess(&blue, "random guess").is_err());
-    assert!(ctx.state.guess(&red, "random guess").is_err());
+    assert!(ctx.state.guess(&blue, PLAYER, "random guess").is_ok());
+    assert!(ctx.state.guess(&red, PLAYER, "random guess").is_ok());
+    assert!(ctx.state.guess(&blue, PLAYER, "random guess").is_err());
+    assert!(ctx.state.guess(&red, PLAYER, "random guess").is_err());
 }
 
 #[test]
 fn only_participants_can_answer() {
     let mut ctx = ContextBuilder::new().build();
     let yellow = TeamId::TeamName("yellow".into());
-    assert!(ctx.state.guess(&yellow, "anything").is_err());
+    assert!(ctx.state.guess(&yellow, PLAYER, "anything").is_err());
 }
 
 #[test]
 fn wrong_answer_deducts_points() {
     let mut ctx = ContextBuilder::new().build();
     let red = ctx.team_ids.get("red").unwrap().clone();
-    assert!(ctx
-        .state
-        .guess(&red, &ctx.state.question.answer[1..].to_string())
-        .is_ok());
+    assert!(ctx.state.guess(&red, PLAYER, "random guess").is_ok());
     let score = ctx.teams.read().iter().find(|t| t.id == red).unwrap().score;
     assert!(score < 0);
     assert_eq!(-1 * ctx.state.question.score_value as i32, score);
@@ -215,7 +208,7 @@ fn correct_answer_gives_points() {
     let red = ctx.team_ids.get("red").unwrap().clone();
     assert!(ctx
         .state
-        .guess(&red, &ctx.state.question.answer.to_string())
+        .guess(&red, PLAYER, &ctx.state.question.answer.to_string())
         .is_ok());
     let score = ctx.teams.read().iter().find(|t| t.id == red).unwrap().score;
     assert!(score > 0);
@@ -229,10 +222,7 @@ fn only_first_answer_gets_full_points() {
     let green = ctx.team_ids.get("green").unwrap().clone();
     let blue = ctx.team_ids.get("blue").unwrap().clone();
 
-    assert!(ctx
-        .state
-        .guess(&red, &ctx.state.question.answer[1..].to_string())
-        .is_ok());
+    assert!(ctx.state.guess(&red, PLAYER, "random guess").is_ok());
     assert_eq!(
         -1 * ctx.state.question.score_value as i32,
         ctx.get_team_score(&red)
@@ -240,17 +230,14 @@ fn only_first_answer_gets_full_points() {
 
     assert!(ctx
         .state
-        .guess(&blue, &ctx.state.question.answer.to_string())
+        .guess(&blue, PLAYER, &ctx.state.question.answer.to_string())
         .is_ok());
     assert_eq!(
         ctx.state.question.score_value as i32 / 2,
         ctx.get_team_score(&blue)
     );
 
-    assert!(ctx
-        .state
-        .guess(&green, &ctx.state.question.answer[1..].to_string())
-        .is_ok());
+    assert!(ctx.state.guess(&green, PLAYER, "random guess").is_ok());
     assert_eq!(
         ctx.state.question.score_value as i32 / -2,
         ctx.get_team_score(&green)
@@ -267,7 +254,7 @@ fn wager_defaults_to_question_value() {
 
     assert!(ctx
         .state
-        .guess(&red, &ctx.state.question.answer.to_string())
+        .guess(&red, PLAYER, &ctx.state.question.answer.to_string())
         .is_ok());
     let score = ctx.teams.read().iter().find(|t| t.id == red).unwrap().score;
     assert_eq!(ctx.state.question.score_value as i32, score);
@@ -293,15 +280,12 @@ fn wager_overrides_question_value() {
 
     assert!(ctx
         .state
-        .guess(&red, &ctx.state.question.answer.to_string())
+        .guess(&red, PLAYER, &ctx.state.question.answer.to_string())
         .is_ok());
     let score = ctx.teams.read().iter().find(|t| t.id == red).unwrap().score;
     assert_eq!(red_wager_amount as i32, score);
 
-    assert!(ctx
-        .state
-        .guess(&blue, &ctx.state.question.answer[1..].to_string())
-        .is_ok());
+    assert!(ctx.state.guess(&blue, PLAYER, "random guess").is_ok());
     let score = ctx
         .teams
         .read()
@@ -323,9 +307,297 @@ fn reveals_answer_after_all_teams_have_guessed() {
         Message::AnswerReveal(_) => true,
         _ => false,
     };
-    assert!(ctx.state.guess(&red, "whatever").is_ok());
-    assert!(ctx.state.guess(&green, "whatever").is_ok());
+    assert!(ctx.state.guess(&red, PLAYER, "whatever").is_ok());
+    assert!(ctx.state.guess(&green, PLAYER, "whatever").is_ok());
     assert!(!ctx.output.flush().iter().any(is_answer_reveal));
-    assert!(ctx.state.guess(&blue, "whatever").is_ok());
+    assert!(ctx.state.guess(&blue, PLAYER, "whatever").is_ok());
     assert!(ctx.output.flush().iter().any(is_answer_reveal));
 }
+
+#[test]
+fn small_typos_are_forgiven() {
+    let mut ctx = ContextBuilder::new().build();
+    let red = ctx.team_ids.get("red").unwrap().clone();
+    let guess_result = ctx.state.guess(&red, PLAYER, "exmaple answer").unwrap();
+    assert!(guess_result.is_correct);
+    assert_eq!(
+        ctx.state.question.score_value as i32,
+        ctx.get_team_score(&red)
+    );
+}
+
+#[test]
+fn close_guess_can_try_again() {
+    let mut ctx = ContextBuilder::new().build();
+    let red = ctx.team_ids.get("red").unwrap().clone();
+    assert!(ctx.state.guess(&red, PLAYER, "exampl answ").is_ok());
+    assert_eq!(0, ctx.get_team_score(&red));
+    assert_eq!(
+        ctx.output.flush(),
+        [Message::GuessClose("exampl answ".into())]
+    );
+    assert!(
+        ctx.state
+            .guess(&red, PLAYER, "example answer")
+            .unwrap()
+            .is_correct
+    );
+}
+
+#[test]
+fn second_close_guess_is_incorrect() {
+    let mut ctx = ContextBuilder::new().build();
+    let red = ctx.team_ids.get("red").unwrap().clone();
+    assert!(ctx.state.guess(&red, PLAYER, "exampl answ").is_ok());
+    assert_eq!(0, ctx.get_team_score(&red));
+    assert!(
+        !ctx.state
+            .guess(&red, PLAYER, "exampl answ")
+            .unwrap()
+            .is_correct
+    );
+    assert_eq!(
+        -(ctx.state.question.score_value as i32),
+        ctx.get_team_score(&red)
+    );
+}
+
+#[test]
+fn exact_match_questions_do_not_forgive_typos() {
+    let builder = ContextBuilder::new();
+    let mut question = builder.question.clone();
+    question.exact_match = true;
+    let mut ctx = ContextBuilder::new().question(question).build();
+    let red = ctx.team_ids.get("red").unwrap().clone();
+    assert!(
+        !ctx.state
+            .guess(&red, PLAYER, "exmaple answer")
+            .unwrap()
+            .is_correct
+    );
+    assert!(ctx.get_team_score(&red) < 0);
+}
+
+#[test]
+fn guess_result_records_player() {
+    let mut ctx = ContextBuilder::new().build();
+    let red = ctx.team_ids.get("red").unwrap().clone();
+    let guess_result = ctx.state.guess(&red, UserId(42), "random guess").unwrap();
+    assert_eq!(guess_result.player, UserId(42));
+}
+
+#[test]
+fn late_solo_player_can_be_admitted() {
+    let mut ctx = ContextBuilder::new().build();
+    let latecomer = TeamId::Player(UserId(7));
+    ctx.teams.write().push(Team::new(latecomer.clone()));
+    ctx.state.admit(&latecomer);
+    assert!(ctx
+        .state
+        .guess(&latecomer, UserId(7), "random guess")
+        .is_ok());
+}
+
+#[test]
+fn challenge_questions_do_not_admit_latecomers() {
+    let red = TeamId::TeamName("red".into());
+    let mut ctx = ContextBuilder::new().wager(&red, 100).build();
+    let latecomer = TeamId::Player(UserId(7));
+    ctx.teams.write().push(Team::new(latecomer.clone()));
+    ctx.state.admit(&latecomer);
+    assert!(ctx
+        .state
+        .guess(&latecomer, UserId(7), "random guess")
+        .is_err());
+}
+
+#[test]
+fn accepting_a_guess_reverses_the_penalty() {
+    let mut ctx = ContextBuilder::new().build();
+    let red = ctx.team_ids.get("red").unwrap().clone();
+    let score_value = ctx.state.question.score_value as i32;
+    ctx.state.guess(&red, PLAYER, "random guess").unwrap();
+    ctx.output.flush();
+
+    let (guess_result, correction) = ctx.state.rejudge(&red, true).unwrap();
+    assert!(guess_result.is_correct);
+    assert!(guess_result.is_first_correct);
+    assert_eq!(correction, 2 * score_value);
+    assert_eq!(ctx.get_team_score(&red), score_value);
+    assert_eq!(
+        ctx.output.flush(),
+        [Message::GuessAccepted(red, 2 * score_value)]
+    );
+}
+
+#[test]
+fn rejudging_later_guesses_uses_half_value() {
+    let mut ctx = ContextBuilder::new().build();
+    let red = ctx.team_ids.get("red").unwrap().clone();
+    let blue = ctx.team_ids.get("blue").unwrap().clone();
+    let score_value = ctx.state.question.score_value as i32;
+    let answer = ctx.state.question.answer.clone();
+    ctx.state.guess(&red, PLAYER, &answer).unwrap();
+    ctx.state.guess(&blue, PLAYER, &answer).unwrap();
+
+    let (_, correction) = ctx.state.rejudge(&blue, false).unwrap();
+    assert_eq!(correction, -score_value);
+    assert_eq!(ctx.get_team_score(&blue), -score_value / 2);
+    let (_, correction) = ctx.state.rejudge(&red, false).unwrap();
+    assert_eq!(correction, -2 * score_value);
+}
+
+#[test]
+fn rejudging_challenge_questions_uses_wager() {
+    let builder = ContextBuilder::new();
+    let mut question = builder.question.clone();
+    question.challenge = true;
+    let red = builder.team_ids.get("red").unwrap().clone();
+    let mut ctx = ContextBuilder::new()
+        .question(question)
+        .wager(&red, 500)
+        .build();
+    ctx.state.guess(&red, PLAYER, "random guess").unwrap();
+    ctx.state.rejudge(&red, true).unwrap();
+    assert_eq!(ctx.get_team_score(&red), 500);
+}
+
+#[test]
+fn cannot_rejudge_missing_or_unchanged_guesses() {
+    let mut ctx = ContextBuilder::new().build();
+    let red = ctx.team_ids.get("red").unwrap().clone();
+    assert!(ctx.state.rejudge(&red, true).is_err());
+    ctx.state.guess(&red, PLAYER, "random guess").unwrap();
+    assert!(ctx.state.rejudge(&red, false).is_err());
+}
+
+#[test]
+fn guesses_are_recorded_in_score_history() {
+    let mut ctx = ContextBuilder::new().build();
+    let red = ctx.team_ids.get("red").unwrap().clone();
+    ctx.state.guess(&red, PLAYER, "random guess").unwrap();
+    ctx.state.rejudge(&red, true).unwrap();
+
+    let teams = ctx.teams.read();
+    let history = &teams.iter().find(|t| t.id == red).unwrap().history;
+    let reasons: Vec<ScoreReason> = history.iter().map(|c| c.reason).collect();
+    assert_eq!(
+        reasons,
+        [ScoreReason::IncorrectGuess, ScoreReason::Rejudged]
+    );
+    assert_eq!(history[0].question, Some("example answer".to_owned()));
+}
+
+#[test]
+fn first_wrong_guess_opens_steal_round() {
+    let mut ctx = ContextBuilder::new().build();
+    ctx.state.allow_steal();
+    let red = ctx.team_ids.get("red").unwrap().clone();
+    assert!(!ctx.state.is_over());
+    ctx.state.guess(&red, PLAYER, "random guess").unwrap();
+    assert_eq!(ctx.state.get_stolen_from(), Some(&red));
+    assert!(ctx.state.is_over());
+    assert_eq!(ctx.state.get_remaining_participants().len(), 2);
+    assert!(!ctx.state.get_remaining_participants().contains(&red));
+}
+
+#[test]
+fn no_steal_round_unless_allowed() {
+    let mut ctx = ContextBuilder::new().build();
+    let red = ctx.team_ids.get("red").unwrap().clone();
+    ctx.state.guess(&red, PLAYER, "random guess").unwrap();
+    assert_eq!(ctx.state.get_stolen_from(), None);
+    assert!(!ctx.state.is_over());
+}
+
+#[test]
+fn no_steal_round_after_correct_guess() {
+    let mut ctx = ContextBuilder::new().build();
+    ctx.state.allow_steal();
+    let red = ctx.team_ids.get("red").unwrap().clone();
+    let blue = ctx.team_ids.get("blue").unwrap().clone();
+    let answer = ctx.state.question.answer.clone();
+    ctx.state.guess(&red, PLAYER, &answer).unwrap();
+    ctx.state.guess(&blue, PLAYER, "random guess").unwrap();
+    assert_eq!(ctx.state.get_stolen_from(), None);
+}
+
+#[test]
+fn reveal_stages_alternate_clips_and_pauses() {
+    let reveal = Reveal::default();
+    let position = |seconds| get_reveal_position(&reveal, Duration::from_secs(seconds));
+    assert_eq!(position(0), (0, true));
+    assert_eq!(position(2), (0, false));
+    assert_eq!(position(4), (1, true));
+    assert_eq!(position(9), (1, false));
+    assert_eq!(position(12), (2, true));
+    assert_eq!(position(30), (3, true));
+}
+
+#[test]
+fn reveal_guesses_are_worth_less_in_later_stages() {
+    let mut question = ContextBuilder::new().question;
+    question.reveal = true;
+    let mut ctx = ContextBuilder::new().question(question).build();
+    ctx.state.set_reveal(&Reveal::default());
+    let red = ctx.team_ids.get("red").unwrap().clone();
+    let answer = ctx.state.question.answer.clone();
+
+    ctx.state.on_tick(Duration::from_millis(100));
+    ctx.state.on_tick(Duration::from_secs(5));
+    assert!(ctx
+        .output
+        .flush()
+        .contains(&Message::RevealStage(Some(Duration::from_secs(5)), 75)));
+
+    let result = ctx.state.guess(&red, PLAYER, &answer).unwrap();
+    assert_eq!(result.reveal_stage, 1);
+    assert_eq!(ctx.get_team_score(&red), 75);
+}
+
+#[test]
+fn reveal_settings_only_apply_to_reveal_questions() {
+    let mut ctx = ContextBuilder::new().build();
+    ctx.state.set_reveal(&Reveal::default());
+    let red = ctx.team_ids.get("red").unwrap().clone();
+    let answer = ctx.state.question.answer.clone();
+
+    ctx.state.on_tick(Duration::from_millis(100));
+    ctx.state.on_tick(Duration::from_secs(5));
+    ctx.state.guess(&red, PLAYER, &answer).unwrap();
+    assert_eq!(ctx.get_team_score(&red), 100);
+}
+
+#[test]
+fn final_round_answers_stay_secret() {
+    let mut question = ContextBuilder::new().question;
+    question.final_round = true;
+    let red = TeamId::TeamName("red".to_owned());
+    let green = TeamId::TeamName("green".to_owned());
+    let blue = TeamId::TeamName("blue".to_owned());
+    let mut ctx = ContextBuilder::new()
+        .question(question)
+        .wager(&red, 200)
+        .wager(&green, 50)
+        .wager(&blue, 0)
+        .build();
+    let answer = ctx.state.question.answer.clone();
+
+    ctx.state.guess(&red, PLAYER, &answer).unwrap();
+    ctx.state.guess(&green, PLAYER, "random guess").unwrap();
+    assert_eq!(
+        ctx.output.flush(),
+        [Message::FinalGuessRecorded, Message::FinalGuessRecorded]
+    );
+
+    ctx.state.on_end();
+    assert!(ctx.output.flush().is_empty());
+    assert_eq!(ctx.get_team_score(&red), 200);
+    assert_eq!(ctx.get_team_score(&green), -50);
+
+    let results = ctx.state.get_final_results();
+    assert_eq!(results.len(), 3);
+    assert!(results.contains(&(red, Some(answer), 200)));
+    assert!(results.contains(&(green, Some("random guess".to_owned()), -50)));
+    assert!(results.contains(&(blue, None, 0)));
+}
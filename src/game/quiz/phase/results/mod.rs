@@ -0,0 +1,109 @@
+use serenity::model::id::UserId;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::game::quiz::definition::Question;
+use crate::game::quiz::phase::FinalResults;
+use crate::game::quiz::State;
+use crate::game::stats::StatsHandle;
+use crate::game::team::Team;
+use crate::game::TeamsHandle;
+use crate::output::{GameOutput, Message, Recipient};
+
+#[cfg(test)]
+mod tests;
+
+const SFX_CONGRATS: &'static str = "assets/congrats.wav";
+
+#[derive(Debug)]
+pub struct ResultsState<O> {
+    teams: TeamsHandle,
+    stats: StatsHandle,
+    contributions: Vec<(UserId, i32)>,
+    final_results: Option<(Question, FinalResults)>,
+    output: O,
+}
+
+impl<O> ResultsState<O> {
+    pub fn new(
+        teams: TeamsHandle,
+        stats: StatsHandle,
+        contributions: Vec<(UserId, i32)>,
+        output: O,
+    ) -> Self {
+        ResultsState {
+            teams,
+            stats,
+            contributions,
+            final_results: None,
+            output,
+        }
+    }
+
+    // The outcome of the final round is kept secret until the results
+    pub fn set_final_results(&mut self, question: Question, results: FinalResults) {
+        self.final_results = Some((question, results));
+    }
+}
+
+impl<O: GameOutput> State for ResultsState<O> {
+    fn on_tick(&mut self, _dt: Duration) {}
+
+    fn on_begin(&mut self) {
+        if let Some((question, results)) = &self.final_results {
+            self.output.say(
+                &Recipient::AllTeams,
+                &Message::FinalRoundResults(question.clone(), results.clone()),
+            );
+        }
+
+        let teams = self.teams.read();
+        let top_score = teams.iter().map(|t| t.score).max();
+        // Teams still tied once there are no tiebreaker questions left all win
+        let winning_teams: Vec<&Team> = teams
+            .iter()
+            .filter(|t| Some(t.score) == top_score)
+            .collect();
+        if let Some(winning_team) = winning_teams.first() {
+            self.output.play_file_audio(Path::new(SFX_CONGRATS)).ok();
+            let message = if winning_teams.len() > 1 {
+                Message::GameTied(winning_teams.iter().map(|t| t.id.clone()).collect())
+            } else {
+                Message::GameResults(winning_team.id.clone())
+            };
+            self.output.say(&Recipient::AllTeams, &message);
+
+            if !self.contributions.is_empty() {
+                self.output.say(
+                    &Recipient::AllTeams,
+                    &Message::PlayerContributions(self.contributions.clone()),
+                );
+            }
+            // Contributions are sorted best first
+            if let Some((mvp, points)) = self.contributions.first() {
+                if *points > 0 {
+                    self.output.say(
+                        &Recipient::AllTeams,
+                        &Message::MostValuablePlayer(*mvp, *points),
+                    );
+                }
+            }
+
+            let players: Vec<UserId> = teams
+                .iter()
+                .flat_map(|t| t.players.iter().copied())
+                .collect();
+            let winners: Vec<UserId> = winning_teams
+                .iter()
+                .flat_map(|t| t.players.iter().copied())
+                .collect();
+            self.stats.write().record_game(&players, &winners);
+        }
+    }
+
+    fn on_end(&mut self) {}
+
+    fn is_over(&self) -> bool {
+        false
+    }
+}
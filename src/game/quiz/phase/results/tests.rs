@@ -0,0 +1,101 @@
+use parking_lot::RwLock;
+use std::sync::Arc;
+
+use super::*;
+use crate::game::quiz::definition::RawQuestion;
+use crate::game::stats::Stats;
+use crate::game::team::{Team, TeamId};
+use crate::output::mock::MockGameOutput;
+
+fn make_teams() -> TeamsHandle {
+    let mut red = Team::new(TeamId::TeamName("red".to_owned()));
+    red.players.insert(UserId(1));
+    red.players.insert(UserId(2));
+    red.score = 300;
+    let mut blue = Team::new(TeamId::TeamName("blue".to_owned()));
+    blue.players.insert(UserId(3));
+    blue.score = 100;
+    Arc::new(RwLock::new(vec![red, blue]))
+}
+
+#[test]
+fn announces_contributions_and_mvp() {
+    let mut output = MockGameOutput::new();
+    let stats = Arc::new(RwLock::new(Stats::default()));
+    let contributions = vec![(UserId(2), 200), (UserId(3), 100), (UserId(1), 100)];
+    let mut state = ResultsState::new(
+        make_teams(),
+        stats.clone(),
+        contributions.clone(),
+        output.clone(),
+    );
+    state.on_begin();
+    assert_eq!(
+        output.flush(),
+        [
+            Message::GameResults(TeamId::TeamName("red".to_owned())),
+            Message::PlayerContributions(contributions),
+            Message::MostValuablePlayer(UserId(2), 200),
+        ]
+    );
+    assert_eq!(stats.read().get_player(UserId(1)).wins, 1);
+    assert_eq!(stats.read().get_player(UserId(3)).wins, 0);
+    assert_eq!(stats.read().get_player(UserId(3)).games_played, 1);
+}
+
+#[test]
+fn no_mvp_without_positive_contribution() {
+    let mut output = MockGameOutput::new();
+    let stats = Arc::new(RwLock::new(Stats::default()));
+    let contributions = vec![(UserId(1), 0), (UserId(3), -100)];
+    let mut state = ResultsState::new(make_teams(), stats, contributions.clone(), output.clone());
+    state.on_begin();
+    assert_eq!(
+        output.flush(),
+        [
+            Message::GameResults(TeamId::TeamName("red".to_owned())),
+            Message::PlayerContributions(contributions),
+        ]
+    );
+}
+
+#[test]
+fn announces_final_round_before_winner() {
+    let mut output = MockGameOutput::new();
+    let stats = Arc::new(RwLock::new(Stats::default()));
+    let mut state = ResultsState::new(make_teams(), stats, Vec::new(), output.clone());
+    let question: Question = RawQuestion {
+        final_round: true,
+        ..RawQuestion::test()
+    }
+    .into();
+    let results = vec![(TeamId::TeamName("blue".to_owned()), None, -100)];
+    state.set_final_results(question.clone(), results.clone());
+    state.on_begin();
+    assert_eq!(
+        output.flush(),
+        [
+            Message::FinalRoundResults(question, results),
+            Message::GameResults(TeamId::TeamName("red".to_owned())),
+        ]
+    );
+}
+
+#[test]
+fn unbroken_ties_share_the_win() {
+    let mut output = MockGameOutput::new();
+    let stats = Arc::new(RwLock::new(Stats::default()));
+    let teams = make_teams();
+    teams.write()[1].score = 300;
+    let mut state = ResultsState::new(teams, stats.clone(), Vec::new(), output.clone());
+    state.on_begin();
+    assert_eq!(
+        output.flush(),
+        [Message::GameTied(vec![
+            TeamId::TeamName("red".to_owned()),
+            TeamId::TeamName("blue".to_owned()),
+        ])]
+    );
+    assert_eq!(stats.read().get_player(UserId(1)).wins, 1);
+    assert_eq!(stats.read().get_player(UserId(3)).wins, 1);
+}
@@ -1,32 +1,51 @@
+use anyhow::*;
 use std::time::Duration;
 
+use crate::game::quiz::definition::{Question, QuizMetadata};
 use crate::game::quiz::State;
 use crate::output::{GameOutput, Message, Recipient};
 use crate::preload;
-use crate::preload::{PreloadHandle, PreloadState};
+use crate::preload::{PreloadHandle, PreloadState, Song};
 
 #[cfg(test)]
 mod tests;
 
+const PROGRESS_ANNOUNCEMENT_INTERVAL: Duration = Duration::from_secs(15);
+
 #[derive(Clone, Debug)]
 pub struct StartupState<O> {
     time_elapsed: Duration,
     time_to_wait: Duration,
     preload_handle: PreloadHandle,
     preload_state: PreloadState,
+    last_progress_announcement: Duration,
+    announced_progress: Option<usize>,
+    questions: Vec<Question>,
+    metadata: Option<QuizMetadata>,
     output: O,
 }
 
 impl<O: GameOutput> StartupState<O> {
-    pub fn new(duration: Duration, song_urls: &Vec<String>, output: O) -> Self {
-        let preload_handle = preload::preload_songs(song_urls).unwrap(); // todo
-        StartupState {
+    pub fn new(
+        duration: Duration,
+        questions: Vec<Question>,
+        metadata: Option<QuizMetadata>,
+        output: O,
+    ) -> Result<Self> {
+        let songs: Vec<Song> = questions.iter().map(|q| q.get_song()).collect();
+        let preload_handle =
+            preload::preload_songs(&songs).context("Could not start downloading songs")?;
+        Ok(StartupState {
             time_elapsed: Duration::default(),
             time_to_wait: duration,
             preload_handle,
             preload_state: PreloadState::InProgress,
+            last_progress_announcement: Duration::default(),
+            announced_progress: None,
+            questions,
+            metadata,
             output,
-        }
+        })
     }
 
     pub fn preload_succeeded(&self) -> bool {
@@ -35,16 +54,95 @@ impl<O: GameOutput> StartupState<O> {
             _ => false,
         }
     }
+
+    pub fn preload_failed(&self) -> bool {
+        matches!(self.preload_state, PreloadState::Failure)
+    }
+
+    pub fn get_failed_questions(&self) -> Vec<(Question, String)> {
+        self.find_questions(&self.preload_handle.get_failures())
+    }
+
+    fn find_questions(&self, failures: &[(Song, String)]) -> Vec<(Question, String)> {
+        let mut failed_questions: Vec<(Question, String)> = self
+            .questions
+            .iter()
+            .filter_map(|question| {
+                let song = question.get_song();
+                failures
+                    .iter()
+                    .find(|(s, _reason)| *s == song)
+                    .map(|(_song, reason)| (question.clone(), reason.clone()))
+            })
+            .collect();
+        failed_questions.sort_by(|(a, _), (b, _)| {
+            (&a.category, a.score_value).cmp(&(&b.category, b.score_value))
+        });
+        failed_questions
+    }
+
+    // Preloading quizzes with many uncached songs takes minutes, so players are kept informed
+    fn announce_progress(&mut self) {
+        if self.time_elapsed < self.last_progress_announcement + PROGRESS_ANNOUNCEMENT_INTERVAL {
+            return;
+        }
+        let progress = self.preload_handle.get_progress();
+        if self.announced_progress == Some(progress.completed) {
+            return;
+        }
+        self.last_progress_announcement = self.time_elapsed;
+        self.announced_progress = Some(progress.completed);
+        self.output.say(
+            &Recipient::AllTeams,
+            &Message::PreloadProgress(
+                progress.completed,
+                progress.total,
+                progress.estimate_remaining_time(self.time_elapsed),
+            ),
+        );
+    }
 }
 
 impl<O: GameOutput> State for StartupState<O> {
     fn on_tick(&mut self, dt: Duration) {
         self.time_elapsed += dt;
+        let was_in_progress = matches!(self.preload_state, PreloadState::InProgress);
+        let was_failed = self.preload_failed();
+        let was_successful = self.preload_succeeded();
         self.preload_state = self.preload_handle.get_state();
+        if was_in_progress && !matches!(self.preload_state, PreloadState::InProgress) {
+            let normalization_failures = self.preload_handle.get_normalization_failures();
+            if !normalization_failures.is_empty() {
+                self.output.say(
+                    &Recipient::Quizmaster,
+                    &Message::NormalizationReport(self.find_questions(&normalization_failures)),
+                );
+            }
+        }
+        match self.preload_state {
+            PreloadState::InProgress => self.announce_progress(),
+            PreloadState::Success if !was_successful && self.announced_progress.is_some() => {
+                let total = self.preload_handle.get_progress().total;
+                self.output
+                    .say(&Recipient::AllTeams, &Message::PreloadComplete(total));
+            }
+            _ => (),
+        }
+        if self.preload_failed() && !was_failed {
+            self.output
+                .say(&Recipient::AllTeams, &Message::PreloadFailed);
+            self.output.say(
+                &Recipient::Quizmaster,
+                &Message::PreloadReport(self.get_failed_questions()),
+            );
+        }
     }
 
     fn on_begin(&mut self) {
-        self.output.say(&Recipient::AllTeams, &Message::QuizRules);
+        self.output.say(
+            &Recipient::AllTeams,
+            &Message::QuizRules(self.metadata.clone()),
+        );
     }
 
     fn on_end(&mut self) {}
@@ -53,7 +151,8 @@ impl<O: GameOutput> State for StartupState<O> {
         let waited = self.time_elapsed >= self.time_to_wait;
         match self.preload_state {
             PreloadState::InProgress => false,
-            PreloadState::Failure => true,
+            // The quizmaster decides whether to continue without the songs that failed
+            PreloadState::Failure => false,
             PreloadState::Success => waited,
         }
     }
@@ -1,13 +1,14 @@
 use super::*;
 use std::time::{Duration, Instant};
 
+use crate::game::quiz::definition::RawQuestion;
 use crate::output::mock::MockGameOutput;
 
 #[test]
 fn ends_after_duration() {
     let duration = Duration::from_secs(10);
     let output = MockGameOutput::new();
-    let mut state = StartupState::new(duration, &Vec::new(), output.clone());
+    let mut state = StartupState::new(duration, Vec::new(), None, output.clone()).unwrap();
     assert!(!state.is_over());
     state.on_begin();
     assert!(!state.is_over());
@@ -32,8 +33,86 @@ fn ends_after_duration() {
 fn prints_rules() {
     let duration = Duration::from_secs(10);
     let mut output = MockGameOutput::new();
-    let mut state = StartupState::new(duration, &Vec::new(), output.clone());
+    let mut state = StartupState::new(duration, Vec::new(), None, output.clone()).unwrap();
     assert!(output.flush().is_empty());
     state.on_begin();
-    assert_eq!(output.flush(), [Message::QuizRules]);
+    assert_eq!(output.flush(), [Message::QuizRules(None)]);
+}
+
+#[test]
+fn prints_quiz_metadata_with_rules() {
+    let duration = Duration::from_secs(10);
+    let mut output = MockGameOutput::new();
+    let metadata = QuizMetadata {
+        title: Some("Video game music".to_owned()),
+        author: Some("Quizmaster".to_owned()),
+        description: None,
+    };
+    let mut state =
+        StartupState::new(duration, Vec::new(), Some(metadata.clone()), output.clone()).unwrap();
+    state.on_begin();
+    assert_eq!(output.flush(), [Message::QuizRules(Some(metadata))]);
+}
+
+#[test]
+fn reports_songs_that_could_not_be_preloaded() {
+    let duration = Duration::from_secs(0);
+    let mut output = MockGameOutput::new();
+    let question: Question = RawQuestion {
+        url: "file:///level-99/missing-song.wav".to_owned(),
+        answer: "missing song".to_owned(),
+        ..RawQuestion::test()
+    }
+    .into();
+    let mut state =
+        StartupState::new(duration, vec![question.clone()], None, output.clone()).unwrap();
+    state.on_begin();
+    output.flush();
+
+    let start_time = Instant::now();
+    while !state.preload_failed() {
+        if Instant::now().duration_since(start_time) > Duration::from_secs(5) {
+            panic!("Timed out waiting for preload to fail");
+        }
+        std::thread::sleep(Duration::from_millis(10));
+        state.on_tick(Duration::from_millis(10));
+    }
+    assert!(!state.is_over());
+
+    let messages = output.flush();
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0], Message::PreloadFailed);
+    match &messages[1] {
+        Message::PreloadReport(failures) => {
+            assert_eq!(failures.len(), 1);
+            assert_eq!(failures[0].0, question);
+        }
+        m => panic!("Unexpected message {:?}", m),
+    }
+}
+
+#[test]
+fn does_not_announce_progress_when_songs_are_ready_quickly() {
+    let path = std::env::temp_dir().join("level-99-startup-ready-song.wav");
+    std::fs::write(&path, b"").unwrap();
+    let duration = Duration::from_secs(0);
+    let mut output = MockGameOutput::new();
+    let question: Question = RawQuestion {
+        url: format!("file://{}", path.to_string_lossy()),
+        answer: "ready song".to_owned(),
+        ..RawQuestion::test()
+    }
+    .into();
+    let mut state = StartupState::new(duration, vec![question], None, output.clone()).unwrap();
+    state.on_begin();
+
+    let start_time = Instant::now();
+    while !state.is_over() {
+        if Instant::now().duration_since(start_time) > Duration::from_secs(5) {
+            panic!("Timed out waiting for preload to succeed");
+        }
+        std::thread::sleep(Duration::from_millis(10));
+        state.on_tick(Duration::from_millis(10));
+    }
+    assert_eq!(output.flush(), [Message::QuizRules(None)]);
 }
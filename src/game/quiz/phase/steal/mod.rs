@@ -0,0 +1,215 @@
+use anyhow::*;
+use serenity::model::id::UserId;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::game::quiz::assets::*;
+use crate::game::quiz::definition::{GuessJudgement, Question};
+use crate::game::quiz::phase::{print_scores, rescore_guess, score_guess, GuessResult};
+use crate::game::quiz::State;
+use crate::game::stats::StatsHandle;
+use crate::game::{TeamId, TeamsHandle};
+use crate::output::{GameOutput, Message, Recipient};
+
+#[cfg(test)]
+mod tests;
+
+// Short window after a wrong guess in which the teams that have not guessed yet can steal the question.
+// A correct steal earns the full value of the question, a wrong one loses half of it.
+pub struct StealState<O: GameOutput> {
+    pub question: Question,
+    time_elapsed: Duration,
+    time_limit: Duration,
+    pub stolen_from: TeamId,
+    stolen_by: Option<TeamId>,
+    // Set when the guess that opened the steal round is accepted after all
+    closed: bool,
+    guesses: HashMap<TeamId, GuessResult>,
+    pub participants: HashSet<TeamId>,
+    teams: TeamsHandle,
+    stats: StatsHandle,
+    output: O,
+}
+
+impl<O: GameOutput> StealState<O> {
+    pub fn new(
+        question: Question,
+        duration: Duration,
+        stolen_from: TeamId,
+        participants: HashSet<TeamId>,
+        teams: TeamsHandle,
+        stats: StatsHandle,
+        output: O,
+    ) -> Self {
+        StealState {
+            question,
+            time_elapsed: Duration::default(),
+            time_limit: duration,
+            stolen_from,
+            stolen_by: None,
+            closed: false,
+            guesses: HashMap::new(),
+            participants,
+            teams,
+            stats,
+            output,
+        }
+    }
+
+    pub fn guess(&mut self, team_id: &TeamId, player: UserId, guess: &str) -> Result<GuessResult> {
+        if !self.participants.contains(team_id) {
+            return Err(anyhow!("Your team is not allowed to steal this question"));
+        }
+        if self.guesses.contains_key(team_id) {
+            return Err(anyhow!("Team already made a guess"));
+        }
+        if self.stolen_by.is_some() {
+            return Err(anyhow!("This question was already stolen"));
+        }
+
+        // There is no time for a second chance, so close guesses are wrong
+        let is_correct = self.question.judge_guess(guess) == GuessJudgement::Correct;
+        let score_delta = self.compute_score_delta(is_correct);
+        let guess_result = GuessResult {
+            player,
+            guess: guess.into(),
+            score_delta,
+            is_correct,
+            is_first_correct: is_correct,
+            reveal_stage: 0,
+        };
+        self.guesses.insert(team_id.clone(), guess_result.clone());
+        score_guess(
+            &self.teams,
+            &self.stats,
+            &self.output,
+            &self.question,
+            team_id,
+            &guess_result,
+        )?;
+
+        if is_correct {
+            self.stolen_by = Some(team_id.clone());
+            self.output.play_file_audio(Path::new(SFX_CORRECT)).ok();
+            self.output.say(
+                &Recipient::AllTeams,
+                &Message::GuessCorrect(team_id.clone(), score_delta),
+            );
+        } else {
+            self.output.play_file_audio(Path::new(SFX_INCORRECT)).ok();
+            self.output.say(
+                &Recipient::AllTeams,
+                &Message::GuessIncorrect(team_id.clone(), score_delta.abs()),
+            );
+        }
+
+        Ok(guess_result)
+    }
+
+    // Overrules the judgement of a steal attempt, returning the corrected guess and the score correction
+    pub fn rejudge(&mut self, team_id: &TeamId, is_correct: bool) -> Result<(GuessResult, i32)> {
+        let previous = self
+            .guesses
+            .get(team_id)
+            .cloned()
+            .context("This team did not try to steal the question")?;
+        if previous.is_correct == is_correct {
+            return Err(anyhow!(
+                "This guess was already {}",
+                if is_correct { "accepted" } else { "rejected" }
+            ));
+        }
+
+        let score_delta = self.compute_score_delta(is_correct);
+        let guess_result = GuessResult {
+            is_correct,
+            score_delta,
+            is_first_correct: is_correct && self.stolen_by.is_none(),
+            ..previous.clone()
+        };
+        let correction = score_delta - previous.score_delta;
+        self.guesses.insert(team_id.clone(), guess_result.clone());
+        if guess_result.is_first_correct {
+            self.stolen_by = Some(team_id.clone());
+        } else if !is_correct && self.stolen_by.as_ref() == Some(team_id) {
+            self.stolen_by = None;
+        }
+        rescore_guess(
+            &self.teams,
+            &self.stats,
+            &self.output,
+            &self.question,
+            team_id,
+            &guess_result,
+            correction,
+        )?;
+
+        let message = if is_correct {
+            Message::GuessAccepted(team_id.clone(), correction)
+        } else {
+            Message::GuessRejected(team_id.clone(), correction)
+        };
+        self.output.say(&Recipient::AllTeams, &message);
+
+        Ok((guess_result, correction))
+    }
+
+    pub fn has_guessed(&self, team_id: &TeamId) -> bool {
+        self.guesses.contains_key(team_id)
+    }
+
+    // Nothing is left to steal once the question turns out to have been answered correctly
+    pub fn close(&mut self) {
+        self.closed = true;
+    }
+
+    fn compute_score_delta(&self, is_correct: bool) -> i32 {
+        let score_value = self.question.score_value as i32;
+        if is_correct {
+            score_value
+        } else {
+            -score_value / 2
+        }
+    }
+}
+
+impl<O: GameOutput> State for StealState<O> {
+    fn on_begin(&mut self) {
+        self.output.say(
+            &Recipient::AllTeams,
+            &Message::StealBegins(
+                self.stolen_from.clone(),
+                self.question.score_value,
+                self.time_limit,
+            ),
+        );
+    }
+
+    fn on_tick(&mut self, dt: Duration) {
+        self.time_elapsed += dt;
+    }
+
+    fn on_end(&mut self) {
+        if self.stolen_by.is_some() || self.closed {
+            self.output.say(
+                &Recipient::AllTeams,
+                &Message::AnswerReveal(self.question.clone()),
+            );
+        } else {
+            self.output.play_file_audio(Path::new(SFX_TIME)).ok();
+            self.output.say(
+                &Recipient::AllTeams,
+                &Message::TimeUp(self.question.clone()),
+            );
+        }
+        print_scores(&self.teams, &self.output);
+    }
+
+    fn is_over(&self) -> bool {
+        self.stolen_by.is_some()
+            || self.closed
+            || self.guesses.len() == self.participants.len()
+            || self.time_elapsed >= self.time_limit
+    }
+}
@@ -0,0 +1,162 @@
+use parking_lot::RwLock;
+use std::sync::Arc;
+
+use super::*;
+use crate::game::quiz::definition::RawQuestion;
+use crate::game::stats::Stats;
+use crate::game::team::Team;
+use crate::output::mock::MockGameOutput;
+
+const PLAYER: UserId = UserId(1);
+
+struct Context {
+    state: StealState<MockGameOutput>,
+    teams: TeamsHandle,
+    output: MockGameOutput,
+}
+
+impl Context {
+    fn new() -> Self {
+        let question: Question = RawQuestion {
+            steal: true,
+            ..RawQuestion::test()
+        }
+        .into();
+        let teams: TeamsHandle = Arc::new(RwLock::new(
+            ["red", "green", "blue"]
+                .iter()
+                .map(|n| Team::new(TeamId::TeamName(n.to_string())))
+                .collect(),
+        ));
+        let output = MockGameOutput::new();
+        let participants = [team("green"), team("blue")].iter().cloned().collect();
+        let state = StealState::new(
+            question,
+            Duration::from_secs(15),
+            team("red"),
+            participants,
+            teams.clone(),
+            Arc::new(RwLock::new(Stats::default())),
+            output.clone(),
+        );
+        Context {
+            state,
+            teams,
+            output,
+        }
+    }
+
+    fn get_team_score(&self, name: &str) -> i32 {
+        self.teams
+            .read()
+            .iter()
+            .find(|t| t.id == team(name))
+            .unwrap()
+            .score
+    }
+}
+
+fn team(name: &str) -> TeamId {
+    TeamId::TeamName(name.to_owned())
+}
+
+#[test]
+fn announces_steal() {
+    let mut ctx = Context::new();
+    ctx.state.on_begin();
+    assert_eq!(
+        ctx.output.flush(),
+        [Message::StealBegins(
+            team("red"),
+            100,
+            Duration::from_secs(15)
+        )]
+    );
+}
+
+#[test]
+fn team_that_guessed_wrong_cannot_steal() {
+    let mut ctx = Context::new();
+    assert!(ctx
+        .state
+        .guess(&team("red"), PLAYER, "example answer")
+        .is_err());
+}
+
+#[test]
+fn correct_steal_earns_full_value_and_ends_round() {
+    let mut ctx = Context::new();
+    let guess_result = ctx
+        .state
+        .guess(&team("blue"), PLAYER, "example answer")
+        .unwrap();
+    assert!(guess_result.is_first_correct);
+    assert_eq!(ctx.get_team_score("blue"), 100);
+    assert!(ctx.state.is_over());
+    assert!(ctx
+        .state
+        .guess(&team("green"), PLAYER, "example answer")
+        .is_err());
+}
+
+#[test]
+fn wrong_steal_loses_half_value() {
+    let mut ctx = Context::new();
+    ctx.state
+        .guess(&team("green"), PLAYER, "random guess")
+        .unwrap();
+    assert_eq!(ctx.get_team_score("green"), -50);
+    assert!(!ctx.state.is_over());
+    ctx.state
+        .guess(&team("blue"), PLAYER, "random guess")
+        .unwrap();
+    assert!(ctx.state.is_over());
+}
+
+#[test]
+fn times_out() {
+    let mut ctx = Context::new();
+    ctx.state.on_tick(Duration::from_secs(15));
+    assert!(ctx.state.is_over());
+    ctx.output.flush();
+    ctx.state.on_end();
+    assert!(ctx
+        .output
+        .flush()
+        .contains(&Message::TimeUp(ctx.state.question.clone())));
+}
+
+#[test]
+fn rejudged_steal_earns_full_value_and_ends_round() {
+    let mut ctx = Context::new();
+    ctx.state
+        .guess(&team("green"), PLAYER, "random guess")
+        .unwrap();
+    ctx.output.flush();
+
+    let (guess_result, correction) = ctx.state.rejudge(&team("green"), true).unwrap();
+    assert!(guess_result.is_first_correct);
+    assert_eq!(correction, 150);
+    assert_eq!(ctx.get_team_score("green"), 100);
+    assert!(ctx.state.is_over());
+    assert_eq!(
+        ctx.output.flush(),
+        [Message::GuessAccepted(team("green"), 150)]
+    );
+
+    ctx.state.rejudge(&team("green"), false).unwrap();
+    assert_eq!(ctx.get_team_score("green"), -50);
+    assert!(!ctx.state.is_over());
+}
+
+#[test]
+fn closed_steal_reveals_answer() {
+    let mut ctx = Context::new();
+    ctx.state.close();
+    assert!(ctx.state.is_over());
+    ctx.state.on_end();
+    assert!(ctx
+        .output
+        .flush()
+        .contains(&Message::AnswerReveal(ctx.state.question.clone())));
+}
@@ -0,0 +1,155 @@
+use anyhow::*;
+use serenity::model::id::UserId;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::game::quiz::assets::*;
+use crate::game::quiz::definition::{GuessJudgement, Question};
+use crate::game::quiz::phase::{play_song, score_guess, GuessResult};
+use crate::game::quiz::State;
+use crate::game::stats::StatsHandle;
+use crate::game::{TeamId, TeamsHandle};
+use crate::output::{AudioHandle, GameOutput, Message, Recipient};
+
+#[cfg(test)]
+mod tests;
+
+// Sudden death between the teams tied for the win, played on a reserve question.
+// The first correct guess earns the value of the question, which breaks the tie, and wrong guesses cost nothing.
+pub struct TiebreakerState<O: GameOutput> {
+    pub question: Question,
+    time_elapsed: Duration,
+    time_limit: Duration,
+    winner: Option<TeamId>,
+    guesses: HashMap<TeamId, GuessResult>,
+    pub participants: HashSet<TeamId>,
+    song_audio: Option<O::Audio>,
+    teams: TeamsHandle,
+    stats: StatsHandle,
+    output: O,
+}
+
+impl<O: GameOutput> TiebreakerState<O> {
+    pub fn new(
+        question: Question,
+        duration: Duration,
+        participants: HashSet<TeamId>,
+        teams: TeamsHandle,
+        stats: StatsHandle,
+        output: O,
+    ) -> Self {
+        TiebreakerState {
+            time_elapsed: Duration::default(),
+            time_limit: question.duration.unwrap_or(duration),
+            question,
+            winner: None,
+            guesses: HashMap::new(),
+            participants,
+            song_audio: None,
+            teams,
+            stats,
+            output,
+        }
+    }
+
+    pub fn guess(&mut self, team_id: &TeamId, player: UserId, guess: &str) -> Result<GuessResult> {
+        if !self.participants.contains(team_id) {
+            return Err(anyhow!("Only the tied teams can answer the tiebreaker"));
+        }
+        if self.guesses.contains_key(team_id) {
+            return Err(anyhow!("Team already made a guess"));
+        }
+        if self.winner.is_some() {
+            return Err(anyhow!("The tie was already broken"));
+        }
+
+        // Sudden death leaves no room for a second chance, so close guesses are wrong
+        let is_correct = self.question.judge_guess(guess) == GuessJudgement::Correct;
+        let score_delta = if is_correct {
+            self.question.score_value as i32
+        } else {
+            0
+        };
+        let guess_result = GuessResult {
+            player,
+            guess: guess.into(),
+            score_delta,
+            is_correct,
+            is_first_correct: is_correct,
+            reveal_stage: 0,
+        };
+        self.guesses.insert(team_id.clone(), guess_result.clone());
+        score_guess(
+            &self.teams,
+            &self.stats,
+            &self.output,
+            &self.question,
+            team_id,
+            &guess_result,
+        )?;
+
+        if is_correct {
+            self.winner = Some(team_id.clone());
+            self.output.play_file_audio(Path::new(SFX_CORRECT)).ok();
+            self.output.say(
+                &Recipient::AllTeams,
+                &Message::GuessCorrect(team_id.clone(), score_delta),
+            );
+        } else {
+            self.output.play_file_audio(Path::new(SFX_INCORRECT)).ok();
+            self.output.say(
+                &Recipient::AllTeams,
+                &Message::TiebreakerMiss(team_id.clone()),
+            );
+        }
+
+        Ok(guess_result)
+    }
+}
+
+impl<O: GameOutput> State for TiebreakerState<O> {
+    fn on_begin(&mut self) {
+        let tied_teams = self
+            .teams
+            .read()
+            .iter()
+            .filter(|t| self.participants.contains(&t.id))
+            .map(|t| t.id.clone())
+            .collect();
+        self.output.say(
+            &Recipient::AllTeams,
+            &Message::TiebreakerBegins(tied_teams, self.question.category.clone()),
+        );
+        self.song_audio = play_song(&self.output, &self.question);
+    }
+
+    fn on_tick(&mut self, dt: Duration) {
+        self.time_elapsed += dt;
+    }
+
+    // Only the song is stopped, so the sound of the winning guess can still be heard
+    fn on_end(&mut self) {
+        if let Some(song_audio) = self.song_audio.take() {
+            song_audio.stop();
+        }
+        if self.winner.is_some() {
+            self.output.say(
+                &Recipient::AllTeams,
+                &Message::AnswerReveal(self.question.clone()),
+            );
+        } else {
+            self.output.play_file_audio(Path::new(SFX_TIME)).ok();
+            self.output.say(
+                &Recipient::AllTeams,
+                &Message::TimeUp(self.question.clone()),
+            );
+        }
+    }
+
+    fn is_over(&self) -> bool {
+        self.winner.is_some()
+            || self.guesses.len() == self.participants.len()
+            || self.time_elapsed >= self.time_limit
+    }
+}
@@ -0,0 +1,112 @@
+use parking_lot::RwLock;
+use std::sync::Arc;
+
+use super::*;
+use crate::game::quiz::definition::RawQuestion;
+use crate::game::stats::Stats;
+use crate::game::team::Team;
+use crate::output::mock::MockGameOutput;
+
+const PLAYER: UserId = UserId(1);
+
+struct Context {
+    state: TiebreakerState<MockGameOutput>,
+    teams: TeamsHandle,
+    output: MockGameOutput,
+}
+
+impl Context {
+    fn new() -> Self {
+        let question: Question = RawQuestion {
+            tiebreaker: true,
+            ..RawQuestion::test()
+        }
+        .into();
+        let teams: TeamsHandle = Arc::new(RwLock::new(
+            ["red", "green", "blue"]
+                .iter()
+                .map(|n| Team::new(TeamId::TeamName(n.to_string())))
+                .collect(),
+        ));
+        let output = MockGameOutput::new();
+        let participants = [team("red"), team("green")].iter().cloned().collect();
+        let state = TiebreakerState::new(
+            question,
+            Duration::from_secs(30),
+            participants,
+            teams.clone(),
+            Arc::new(RwLock::new(Stats::default())),
+            output.clone(),
+        );
+        Context {
+            state,
+            teams,
+            output,
+        }
+    }
+
+    fn get_team_score(&self, name: &str) -> i32 {
+        self.teams
+            .read()
+            .iter()
+            .find(|t| t.id == team(name))
+            .unwrap()
+            .score
+    }
+}
+
+fn team(name: &str) -> TeamId {
+    TeamId::TeamName(name.to_owned())
+}
+
+#[test]
+fn announces_tied_teams() {
+    let mut ctx = Context::new();
+    ctx.state.on_begin();
+    assert_eq!(
+        ctx.output.flush(),
+        [Message::TiebreakerBegins(
+            vec![team("red"), team("green")],
+            "example category".to_owned()
+        )]
+    );
+}
+
+#[test]
+fn only_tied_teams_can_answer() {
+    let mut ctx = Context::new();
+    assert!(ctx
+        .state
+        .guess(&team("blue"), PLAYER, "example answer")
+        .is_err());
+}
+
+#[test]
+fn first_correct_guess_wins() {
+    let mut ctx = Context::new();
+    ctx.state
+        .guess(&team("red"), PLAYER, "random guess")
+        .unwrap();
+    assert_eq!(ctx.get_team_score("red"), 0);
+    assert!(!ctx.state.is_over());
+
+    ctx.state
+        .guess(&team("green"), PLAYER, "example answer")
+        .unwrap();
+    assert_eq!(ctx.get_team_score("green"), 100);
+    assert_eq!(ctx.state.winner, Some(team("green")));
+    assert!(ctx.state.is_over());
+}
+
+#[test]
+fn times_out_without_winner() {
+    let mut ctx = Context::new();
+    ctx.state.on_tick(Duration::from_secs(30));
+    assert!(ctx.state.is_over());
+    assert_eq!(ctx.state.winner, None);
+    ctx.state.on_end();
+    assert!(ctx
+        .output
+        .flush()
+        .contains(&Message::TimeUp(ctx.state.question.clone())));
+}
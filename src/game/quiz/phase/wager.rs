@@ -7,7 +7,7 @@ use crate::game::quiz::assets::*;
 use crate::game::quiz::definition::Question;
 use crate::game::quiz::State;
 use crate::game::{TeamId, TeamsHandle};
-use crate::output::{GameOutput, Message, Recipient};
+use crate::output::{Event, GameOutput, Message, Recipient};
 
 #[derive(Clone, Debug)]
 pub struct WagerState<O> {
@@ -47,11 +47,16 @@ impl<O: GameOutput> WagerState<O> {
             return Err(anyhow!("Your team is not allowed to wager."));
         }
         let wager_cap = self.get_wager_cap(team_id);
-        let amount = amount.min(wager_cap).max(self.question.score_value);
+        let amount = amount.min(wager_cap).max(self.get_minimum_wager());
         self.wagers.insert(team_id.clone(), amount);
+        self.output.record(&Event::Wager {
+            team_id: team_id.clone(),
+            amount,
+        });
         Ok(())
     }
 
+    // Wagers of the final round are blind and can be anything up to the team's whole score
     fn get_wager_cap(&self, team_id: &TeamId) -> u32 {
         let team_score = self
             .teams
@@ -60,7 +65,34 @@ impl<O: GameOutput> WagerState<O> {
             .find(|t| &t.id == team_id)
             .and_then(|t| Some(t.score.max(0) as u32))
             .unwrap_or(0);
-        team_score.max(2 * self.max_question_score_value)
+        if self.question.final_round {
+            team_score
+        } else {
+            team_score.max(2 * self.max_question_score_value)
+        }
+    }
+
+    fn get_minimum_wager(&self) -> u32 {
+        if self.question.final_round {
+            0
+        } else {
+            self.question.score_value
+        }
+    }
+
+    // Participants who did not wager bet the minimum
+    pub fn get_wagers(&self) -> HashMap<TeamId, u32> {
+        self.participants
+            .iter()
+            .map(|team_id| {
+                let amount = self
+                    .wagers
+                    .get(team_id)
+                    .copied()
+                    .unwrap_or_else(|| self.get_minimum_wager());
+                (team_id.clone(), amount)
+            })
+            .collect()
     }
 
     fn did_every_team_wager(&self) -> bool {
@@ -104,37 +136,37 @@ impl<O: GameOutput> State for WagerState<O> {
 
     fn on_begin(&mut self) {
         self.output.play_file_audio(Path::new(SFX_CHALLENGE)).ok();
-        self.output.say(
-            &Recipient::AllTeams,
-            &Message::WagerBegins(self.question.category.clone()),
-        );
+        let (begins, wait) = if self.question.final_round {
+            (
+                Message::FinalRoundBegins(self.question.category.clone()),
+                Message::FinalRoundSitOut,
+            )
+        } else {
+            (
+                Message::WagerBegins(self.question.category.clone()),
+                Message::WagerWait,
+            )
+        };
+        self.output.say(&Recipient::AllTeams, &begins);
         for team in self.teams.read().iter() {
             if self.participants.contains(&team.id) {
                 let wager_cap = self.get_wager_cap(&team.id);
                 self.output.say(
                     &Recipient::Team(team.id.clone()),
-                    &Message::WagerRules(self.question.score_value, wager_cap),
+                    &Message::WagerRules(self.get_minimum_wager(), wager_cap),
                 );
             } else {
-                self.output
-                    .say(&Recipient::Team(team.id.clone()), &Message::WagerWait);
+                self.output.say(&Recipient::Team(team.id.clone()), &wait);
             }
         }
     }
 
     fn on_end(&mut self) {
-        let wagers = self
-            .participants
-            .iter()
-            .map(|team_id| {
-                let amount = self
-                    .wagers
-                    .get(team_id)
-                    .copied()
-                    .unwrap_or(self.question.score_value);
-                (team_id.clone(), amount)
-            })
-            .collect();
+        // Final round wagers stay secret until the results
+        if self.question.final_round {
+            return;
+        }
+        let wagers = self.get_wagers().into_iter().collect();
         self.output
             .say(&Recipient::AllTeams, &Message::WagerResults(wagers));
     }
@@ -0,0 +1,245 @@
+use anyhow::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[cfg(test)]
+mod tests;
+
+// Steal window of questions that allow steals when the quiz does not set one
+pub const DEFAULT_STEAL_DURATION: Duration = Duration::from_secs(15);
+
+// Clips of increasing length played by progressive reveal questions before the full song
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Reveal {
+    pub stages: Vec<Duration>,
+    // Percentage of the question's value earned during each stage, the full song being the last one
+    pub multipliers: Vec<u32>,
+}
+
+impl Default for Reveal {
+    fn default() -> Self {
+        Reveal {
+            stages: vec![
+                Duration::from_secs(2),
+                Duration::from_secs(5),
+                Duration::from_secs(10),
+            ],
+            multipliers: vec![100, 75, 50, 25],
+        }
+    }
+}
+
+impl Reveal {
+    // Stages without a multiplier of their own use the last one
+    pub fn get_multiplier(&self, stage: usize) -> u32 {
+        self.multipliers
+            .get(stage)
+            .or_else(|| self.multipliers.last())
+            .copied()
+            .unwrap_or(100)
+    }
+}
+
+#[derive(Debug)]
+pub struct Settings {
+    pub startup_duration: Duration,
+    pub vote_duration: Duration,
+    pub wager_duration: Duration,
+    pub question_duration: Duration,
+    pub cooldown_duration: Duration,
+    pub max_vote_options: usize,
+    // Window for other teams to steal a question after a wrong guess, when every question allows it
+    pub steal_duration: Option<Duration>,
+    pub reveal: Reveal,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            startup_duration: Duration::from_secs(30),
+            vote_duration: Duration::from_secs(15),
+            wager_duration: Duration::from_secs(90),
+            question_duration: Duration::from_secs(90),
+            cooldown_duration: Duration::from_secs(5),
+            max_vote_options: 6,
+            steal_duration: None,
+            reveal: Reveal::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn apply(&mut self, overrides: &SettingsOverrides) {
+        if let Some(seconds) = overrides.startup {
+            self.startup_duration = Duration::from_secs(seconds);
+        }
+        if let Some(seconds) = overrides.vote {
+            self.vote_duration = Duration::from_secs(seconds);
+        }
+        if let Some(seconds) = overrides.wager {
+            self.wager_duration = Duration::from_secs(seconds);
+        }
+        if let Some(seconds) = overrides.question {
+            self.question_duration = Duration::from_secs(seconds);
+        }
+        if let Some(seconds) = overrides.cooldown {
+            self.cooldown_duration = Duration::from_secs(seconds);
+        }
+        if let Some(options) = overrides.options {
+            self.max_vote_options = options;
+        }
+        if let Some(seconds) = overrides.steal {
+            self.steal_duration = match seconds {
+                0 => None,
+                seconds => Some(Duration::from_secs(seconds)),
+            };
+        }
+        if let Some(stages) = &overrides.reveal_stages {
+            self.reveal.stages = stages.iter().map(|s| Duration::from_secs(*s)).collect();
+        }
+        if let Some(multipliers) = &overrides.reveal_multipliers {
+            self.reveal.multipliers = multipliers.clone();
+        }
+    }
+}
+
+// Durations are in seconds. The same keys are used in quiz files, sidecar files and `!begin` arguments.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SettingsOverrides {
+    pub startup: Option<u64>,
+    pub vote: Option<u64>,
+    pub wager: Option<u64>,
+    pub question: Option<u64>,
+    pub cooldown: Option<u64>,
+    pub options: Option<usize>,
+    // Zero turns steals off
+    pub steal: Option<u64>,
+    pub reveal_stages: Option<Vec<u64>>,
+    // Percentages
+    pub reveal_multipliers: Option<Vec<u32>>,
+}
+
+impl SettingsOverrides {
+    pub fn from_toml(content: &str) -> Result<SettingsOverrides> {
+        let overrides: SettingsOverrides =
+            toml::from_str(content).context("Could not parse quiz settings")?;
+        overrides.check()?;
+        Ok(overrides)
+    }
+
+    // Parses arguments like `vote=20 options=4`
+    pub fn from_arguments<'a, I: IntoIterator<Item = &'a str>>(
+        arguments: I,
+    ) -> Result<SettingsOverrides> {
+        let mut overrides = SettingsOverrides::default();
+        for argument in arguments {
+            let (key, value) = match argument.find('=') {
+                Some(index) => (&argument[..index], &argument[index + 1..]),
+                None => return Err(anyhow!("Setting `{}` must look like `key=value`", argument)),
+            };
+            let seconds = || -> Result<Option<u64>> {
+                let seconds = value
+                    .parse()
+                    .with_context(|| format!("Could not parse `{}` as seconds", value))?;
+                Ok(Some(seconds))
+            };
+            match key {
+                "startup" => overrides.startup = seconds()?,
+                "vote" => overrides.vote = seconds()?,
+                "wager" => overrides.wager = seconds()?,
+                "question" => overrides.question = seconds()?,
+                "cooldown" => overrides.cooldown = seconds()?,
+                "steal" => overrides.steal = seconds()?,
+                "reveal_stages" => overrides.reveal_stages = Some(parse_list(value)?),
+                "reveal_multipliers" => overrides.reveal_multipliers = Some(parse_list(value)?),
+                "options" => {
+                    let options = value
+                        .parse()
+                        .with_context(|| format!("Could not parse `{}` as a number", value))?;
+                    overrides.options = Some(options);
+                }
+                _ => return Err(anyhow!("Unknown setting `{}`", key)),
+            }
+        }
+        overrides.check()?;
+        Ok(overrides)
+    }
+
+    // Splits `path/to/quiz.csv vote=20 options=4` into a path and the settings after it
+    pub fn split_from_path(input: &str) -> Result<(&str, SettingsOverrides)> {
+        let mut path = input.trim();
+        let mut arguments = Vec::new();
+        while let Some(index) = path.rfind(char::is_whitespace) {
+            let argument = &path[index + 1..];
+            if !argument.contains('=') {
+                break;
+            }
+            arguments.insert(0, argument);
+            path = path[..index].trim_end();
+        }
+        Ok((path, SettingsOverrides::from_arguments(arguments)?))
+    }
+
+    pub fn check(&self) -> Result<()> {
+        if self.options == Some(0) {
+            return Err(anyhow!("Votes need at least one option"));
+        }
+        if self.question == Some(0) {
+            return Err(anyhow!("Questions cannot last zero seconds"));
+        }
+        if let Some(stages) = &self.reveal_stages {
+            if stages.is_empty() || stages.contains(&0) {
+                return Err(anyhow!("Reveal stages must last at least one second"));
+            }
+            if stages.windows(2).any(|w| w[0] >= w[1]) {
+                return Err(anyhow!("Reveal stages must get longer"));
+            }
+        }
+        if self.reveal_multipliers.as_ref().map(Vec::is_empty) == Some(true) {
+            return Err(anyhow!("Reveal multipliers cannot be empty"));
+        }
+        if let (Some(stages), Some(multipliers)) = (&self.reveal_stages, &self.reveal_multipliers) {
+            if multipliers.len() != stages.len() + 1 {
+                return Err(anyhow!(
+                    "Reveal questions need one multiplier per stage, plus one for the full song"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // Fields set in `other` take precedence over fields set in `self`.
+    // Settings that are valid on their own can conflict once merged, so call `check` on the result.
+    pub fn merge(&self, other: &SettingsOverrides) -> SettingsOverrides {
+        SettingsOverrides {
+            startup: other.startup.or(self.startup),
+            vote: other.vote.or(self.vote),
+            wager: other.wager.or(self.wager),
+            question: other.question.or(self.question),
+            cooldown: other.cooldown.or(self.cooldown),
+            options: other.options.or(self.options),
+            steal: other.steal.or(self.steal),
+            reveal_stages: other
+                .reveal_stages
+                .clone()
+                .or_else(|| self.reveal_stages.clone()),
+            reveal_multipliers: other
+                .reveal_multipliers
+                .clone()
+                .or_else(|| self.reveal_multipliers.clone()),
+        }
+    }
+}
+
+// Parses comma-separated lists like `2,5,10`
+fn parse_list<T: std::str::FromStr>(value: &str) -> Result<Vec<T>> {
+    value
+        .split(',')
+        .map(|item| {
+            item.trim()
+                .parse()
+                .map_err(|_| anyhow!("Could not parse `{}` as a number", item))
+        })
+        .collect()
+}
@@ -0,0 +1,112 @@
+use super::*;
+
+#[test]
+fn overrides_replace_defaults() {
+    let mut settings = Settings::default();
+    let overrides = SettingsOverrides {
+        vote: Some(20),
+        options: Some(4),
+        ..Default::default()
+    };
+    settings.apply(&overrides);
+    assert_eq!(settings.vote_duration, Duration::from_secs(20));
+    assert_eq!(settings.max_vote_options, 4);
+    assert_eq!(settings.question_duration, Duration::from_secs(90));
+}
+
+#[test]
+fn parses_arguments() {
+    let overrides = SettingsOverrides::from_arguments(vec!["vote=20", "options=4"]).unwrap();
+    assert_eq!(overrides.vote, Some(20));
+    assert_eq!(overrides.options, Some(4));
+    assert_eq!(overrides.question, None);
+}
+
+#[test]
+fn rejects_invalid_arguments() {
+    assert!(SettingsOverrides::from_arguments(vec!["vote"]).is_err());
+    assert!(SettingsOverrides::from_arguments(vec!["vote=soon"]).is_err());
+    assert!(SettingsOverrides::from_arguments(vec!["tempo=20"]).is_err());
+    assert!(SettingsOverrides::from_arguments(vec!["options=0"]).is_err());
+}
+
+#[test]
+fn splits_arguments_from_path() {
+    let (path, overrides) =
+        SettingsOverrides::split_from_path("My Quizzes/quiz.csv vote=20 options=4").unwrap();
+    assert_eq!(path, "My Quizzes/quiz.csv");
+    assert_eq!(overrides.vote, Some(20));
+    assert_eq!(overrides.options, Some(4));
+
+    let (path, overrides) = SettingsOverrides::split_from_path("quiz.csv").unwrap();
+    assert_eq!(path, "quiz.csv");
+    assert_eq!(overrides, SettingsOverrides::default());
+}
+
+#[test]
+fn later_overrides_take_precedence() {
+    let file = SettingsOverrides::from_toml("vote = 30\nquestion = 60").unwrap();
+    let arguments = SettingsOverrides::from_arguments(vec!["vote=20"]).unwrap();
+    let merged = file.merge(&arguments);
+    assert_eq!(merged.vote, Some(20));
+    assert_eq!(merged.question, Some(60));
+}
+
+#[test]
+fn zero_steal_duration_turns_steals_off() {
+    let mut settings = Settings::default();
+    assert_eq!(settings.steal_duration, None);
+    settings.apply(&SettingsOverrides::from_arguments(vec!["steal=20"]).unwrap());
+    assert_eq!(settings.steal_duration, Some(Duration::from_secs(20)));
+    settings.apply(&SettingsOverrides::from_arguments(vec!["steal=0"]).unwrap());
+    assert_eq!(settings.steal_duration, None);
+}
+
+#[test]
+fn parses_reveal_stages_and_multipliers() {
+    let mut settings = Settings::default();
+    let overrides = SettingsOverrides::from_arguments(vec![
+        "reveal_stages=3,6",
+        "reveal_multipliers=100,50,10",
+    ])
+    .unwrap();
+    settings.apply(&overrides);
+    assert_eq!(
+        settings.reveal.stages,
+        [Duration::from_secs(3), Duration::from_secs(6)]
+    );
+    assert_eq!(settings.reveal.get_multiplier(2), 10);
+    assert_eq!(settings.reveal.get_multiplier(5), 10);
+
+    let file = SettingsOverrides::from_toml("reveal_stages = [2, 4]").unwrap();
+    assert_eq!(file.reveal_stages, Some(vec![2, 4]));
+}
+
+#[test]
+fn rejects_invalid_reveal_stages() {
+    assert!(SettingsOverrides::from_arguments(vec!["reveal_stages=5,2"]).is_err());
+    assert!(SettingsOverrides::from_arguments(vec!["reveal_stages=0,2"]).is_err());
+    assert!(SettingsOverrides::from_arguments(vec!["reveal_multipliers=100,x"]).is_err());
+}
+
+#[test]
+fn rejects_mismatched_reveal_multipliers() {
+    assert!(SettingsOverrides::from_arguments(vec![
+        "reveal_stages=3,6",
+        "reveal_multipliers=100,50",
+    ])
+    .is_err());
+    assert!(SettingsOverrides::from_toml(
+        "reveal_stages = [2]\nreveal_multipliers = [100, 75, 50]"
+    )
+    .is_err());
+}
+
+#[test]
+fn merged_overrides_can_conflict() {
+    let file =
+        SettingsOverrides::from_toml("reveal_stages = [2, 4]\nreveal_multipliers = [100, 50, 25]")
+            .unwrap();
+    let arguments = SettingsOverrides::from_arguments(vec!["reveal_stages=3"]).unwrap();
+    assert!(file.merge(&arguments).check().is_err());
+}
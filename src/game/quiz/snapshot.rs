@@ -0,0 +1,106 @@
+use anyhow::*;
+use directories_next::BaseDirs;
+use serde::{Deserialize, Serialize};
+use serenity::model::id::UserId;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::game::quiz::definition::Question;
+use crate::game::quiz::phase::FinalResults;
+use crate::game::quiz::settings::SettingsOverrides;
+use crate::game::team::{Team, TeamId};
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct QuestionKey {
+    pub url: String,
+    pub category: String,
+    pub score_value: u32,
+}
+
+impl From<&Question> for QuestionKey {
+    fn from(question: &Question) -> Self {
+        QuestionKey {
+            url: question.url.clone(),
+            category: question.category.clone(),
+            score_value: question.score_value,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum PhaseSnapshot {
+    Startup,
+    Cooldown,
+    Vote,
+    Wager {
+        question: QuestionKey,
+        participants: HashSet<TeamId>,
+    },
+    Question {
+        question: QuestionKey,
+        participants: HashSet<TeamId>,
+        wagers: Option<Vec<(TeamId, u32)>>,
+    },
+    Steal {
+        question: QuestionKey,
+        stolen_from: TeamId,
+        participants: HashSet<TeamId>,
+    },
+    Tiebreaker {
+        question: QuestionKey,
+        participants: HashSet<TeamId>,
+    },
+    Results,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Snapshot {
+    pub quiz_path: PathBuf,
+    #[serde(default)]
+    pub settings_overrides: SettingsOverrides,
+    pub teams: Vec<Team>,
+    pub initiative: Option<TeamId>,
+    #[serde(default)]
+    pub contributions: Vec<(UserId, i32)>,
+    pub remaining_questions: Vec<QuestionKey>,
+    #[serde(default)]
+    pub final_results: Option<(QuestionKey, FinalResults)>,
+    pub phase: PhaseSnapshot,
+}
+
+impl Snapshot {
+    pub fn read(path: &Path) -> Result<Snapshot> {
+        let content = fs::read_to_string(path).context("No interrupted quiz to resume")?;
+        serde_json::from_str(&content).context("Could not parse quiz snapshot")
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Write to a temporary file first so a crash mid-write cannot corrupt the previous snapshot
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    pub fn delete(path: &Path) -> Result<()> {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn get_snapshot_path(name: &str) -> Result<PathBuf> {
+    let mut path = BaseDirs::new()
+        .context("could not locate system directories")?
+        .data_local_dir()
+        .to_path_buf();
+    path.push("level-99");
+    path.push("snapshots");
+    path.push(format!("{}.json", name));
+    Ok(path)
+}
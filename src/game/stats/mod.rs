@@ -0,0 +1,254 @@
+use anyhow::*;
+use directories_next::BaseDirs;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use serenity::model::id::UserId;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[cfg(test)]
+mod tests;
+
+const FAVOURITE_CATEGORIES_LISTED: usize = 3;
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
+pub struct PlayerStats {
+    pub games_played: u32,
+    pub wins: u32,
+    pub correct_guesses: u32,
+    pub incorrect_guesses: u32,
+    pub points: i64,
+    // Number of correct guesses in each category
+    pub categories: HashMap<String, u32>,
+}
+
+impl PlayerStats {
+    pub fn get_favourite_categories(&self) -> Vec<(&str, u32)> {
+        let mut categories: Vec<(&str, u32)> = self
+            .categories
+            .iter()
+            .map(|(category, count)| (category.as_str(), *count))
+            .collect();
+        categories.sort_by(|(a_name, a_count), (b_name, b_count)| {
+            b_count.cmp(a_count).then(a_name.cmp(b_name))
+        });
+        categories.truncate(FAVOURITE_CATEGORIES_LISTED);
+        categories
+    }
+
+    fn remove_category_guess(&mut self, category: &str) {
+        let remaining = self.categories.get(category).copied().unwrap_or(0);
+        if remaining <= 1 {
+            self.categories.remove(category);
+        } else {
+            self.categories.insert(category.to_owned(), remaining - 1);
+        }
+    }
+}
+
+// Statistics about every player who took part in a quiz on one server, kept across bot sessions
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Stats {
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    // Set when guesses were recorded since the statistics were last written
+    #[serde(skip)]
+    unsaved: bool,
+    // Keyed by user ID
+    players: HashMap<u64, PlayerStats>,
+}
+
+pub type StatsHandle = Arc<RwLock<Stats>>;
+
+impl Stats {
+    // Missing or unreadable statistics start over from scratch rather than preventing quizzes
+    pub fn open(path: Option<PathBuf>) -> Stats {
+        let mut stats = path
+            .as_ref()
+            .filter(|p| p.exists())
+            .map(|p| Stats::read(p))
+            .unwrap_or_else(|| Ok(Stats::default()))
+            .unwrap_or_else(|e| {
+                eprintln!("Could not read player statistics: {:#}", e);
+                Stats::default()
+            });
+        stats.path = path;
+        stats
+    }
+
+    fn read(path: &Path) -> Result<Stats> {
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content).context("Could not parse player statistics")
+    }
+
+    // Guesses are only kept in memory until this is called, so guessing does not rewrite the file
+    pub fn save(&mut self) {
+        if !self.unsaved {
+            return;
+        }
+        self.unsaved = false;
+        let path = match &self.path {
+            Some(p) => p,
+            None => return,
+        };
+        let result = || -> Result<()> {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let temp_path = path.with_extension("tmp");
+            fs::write(&temp_path, serde_json::to_string_pretty(self)?)?;
+            fs::rename(&temp_path, path)?;
+            Ok(())
+        }();
+        if let Err(e) = result {
+            eprintln!("Could not save player statistics: {:#}", e);
+        }
+    }
+
+    pub fn get_player(&self, player: UserId) -> PlayerStats {
+        self.players.get(&player.0).cloned().unwrap_or_default()
+    }
+
+    // Players ranked by wins, then by points
+    pub fn get_leaderboard(&self, max_entries: usize) -> Vec<(UserId, PlayerStats)> {
+        let mut players: Vec<(UserId, PlayerStats)> = self
+            .players
+            .iter()
+            .map(|(id, stats)| (UserId(*id), stats.clone()))
+            .collect();
+        players.sort_by(|(a_id, a), (b_id, b)| {
+            (b.wins, b.points)
+                .cmp(&(a.wins, a.points))
+                .then(a_id.cmp(b_id))
+        });
+        players.truncate(max_entries);
+        players
+    }
+
+    pub fn record_guess(
+        &mut self,
+        player: UserId,
+        category: &str,
+        is_correct: bool,
+        score_delta: i32,
+    ) {
+        let stats = self.players.entry(player.0).or_default();
+        if is_correct {
+            stats.correct_guesses += 1;
+            *stats.categories.entry(category.to_owned()).or_default() += 1;
+        } else {
+            stats.incorrect_guesses += 1;
+        }
+        stats.points += i64::from(score_delta);
+        self.unsaved = true;
+    }
+
+    // Moves a guess that was judged again by the quizmaster to the other side of the tally
+    pub fn rejudge_guess(
+        &mut self,
+        player: UserId,
+        category: &str,
+        is_correct: bool,
+        correction: i32,
+    ) {
+        let stats = self.players.entry(player.0).or_default();
+        if is_correct {
+            stats.incorrect_guesses = stats.incorrect_guesses.saturating_sub(1);
+            stats.correct_guesses += 1;
+            *stats.categories.entry(category.to_owned()).or_default() += 1;
+        } else {
+            stats.correct_guesses = stats.correct_guesses.saturating_sub(1);
+            stats.remove_category_guess(category);
+            stats.incorrect_guesses += 1;
+        }
+        stats.points += i64::from(correction);
+        self.unsaved = true;
+    }
+
+    // Takes back a guess whose score change was undone by the quizmaster
+    pub fn forget_guess(
+        &mut self,
+        player: UserId,
+        category: &str,
+        is_correct: bool,
+        score_delta: i32,
+    ) {
+        let stats = self.players.entry(player.0).or_default();
+        if is_correct {
+            stats.correct_guesses = stats.correct_guesses.saturating_sub(1);
+            stats.remove_category_guess(category);
+        } else {
+            stats.incorrect_guesses = stats.incorrect_guesses.saturating_sub(1);
+        }
+        stats.points -= i64::from(score_delta);
+        self.unsaved = true;
+    }
+
+    pub fn record_game(&mut self, players: &[UserId], winners: &[UserId]) {
+        for player in players {
+            let stats = self.players.entry(player.0).or_default();
+            stats.games_played += 1;
+            if winners.contains(player) {
+                stats.wins += 1;
+            }
+        }
+        self.unsaved = true;
+        self.save();
+    }
+}
+
+pub fn get_stats_path(name: &str) -> Result<PathBuf> {
+    let mut path = BaseDirs::new()
+        .context("could not locate system directories")?
+        .data_local_dir()
+        .to_path_buf();
+    path.push("level-99");
+    path.push("stats");
+    path.push(format!("{}.json", name));
+    Ok(path)
+}
+
+pub fn describe_player(name: &str, stats: &PlayerStats) -> String {
+    let mut description = format!(
+        "{}: {} game(s) played, {} win(s), {} correct and {} incorrect guess(es), {} points",
+        name,
+        stats.games_played,
+        stats.wins,
+        stats.correct_guesses,
+        stats.incorrect_guesses,
+        stats.points
+    );
+    let favourites = stats.get_favourite_categories();
+    if !favourites.is_empty() {
+        let favourites: Vec<String> = favourites
+            .iter()
+            .map(|(category, count)| format!("{} ({})", category, count))
+            .collect();
+        description.push_str(&format!(
+            "\nFavourite categories: {}",
+            favourites.join(", ")
+        ));
+    }
+    description
+}
+
+pub fn describe_leaderboard(entries: &[(String, PlayerStats)]) -> String {
+    if entries.is_empty() {
+        return "Nobody has played a quiz yet".to_owned();
+    }
+    let mut description = "Leaderboard:".to_owned();
+    for (index, (name, stats)) in entries.iter().enumerate() {
+        description.push_str(&format!(
+            "\n{}. {}: {} win(s) in {} game(s), {} points",
+            index + 1,
+            name,
+            stats.wins,
+            stats.games_played,
+            stats.points
+        ));
+    }
+    description
+}
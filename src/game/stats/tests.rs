@@ -0,0 +1,100 @@
+use super::*;
+use std::env;
+
+#[test]
+fn records_guesses_and_games() {
+    let mut stats = Stats::default();
+    let alice = UserId(1);
+    let bob = UserId(2);
+
+    stats.record_guess(alice, "Platformers", true, 100);
+    stats.record_guess(alice, "Platformers", true, 200);
+    stats.record_guess(alice, "RPGs", false, -100);
+    stats.record_guess(bob, "RPGs", true, 50);
+    stats.record_game(&[alice, bob], &[bob]);
+
+    let alice_stats = stats.get_player(alice);
+    assert_eq!(alice_stats.games_played, 1);
+    assert_eq!(alice_stats.wins, 0);
+    assert_eq!(alice_stats.correct_guesses, 2);
+    assert_eq!(alice_stats.incorrect_guesses, 1);
+    assert_eq!(alice_stats.points, 200);
+    assert_eq!(alice_stats.get_favourite_categories(), [("Platformers", 2)]);
+
+    let bob_stats = stats.get_player(bob);
+    assert_eq!(bob_stats.wins, 1);
+    assert_eq!(stats.get_player(UserId(3)), PlayerStats::default());
+}
+
+#[test]
+fn ranks_players_by_wins_then_points() {
+    let mut stats = Stats::default();
+    stats.record_guess(UserId(1), "Platformers", true, 500);
+    stats.record_guess(UserId(2), "Platformers", true, 100);
+    stats.record_guess(UserId(3), "Platformers", true, 300);
+    stats.record_game(&[UserId(1), UserId(2), UserId(3)], &[UserId(2), UserId(3)]);
+
+    let ranking: Vec<UserId> = stats
+        .get_leaderboard(10)
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect();
+    assert_eq!(ranking, [UserId(3), UserId(2), UserId(1)]);
+    assert_eq!(stats.get_leaderboard(1).len(), 1);
+}
+
+#[test]
+fn persists_across_sessions() {
+    let path = env::temp_dir().join(format!("level-99-stats-{}.json", std::process::id()));
+    if path.exists() {
+        fs::remove_file(&path).unwrap();
+    }
+
+    let mut stats = Stats::open(Some(path.clone()));
+    stats.record_guess(UserId(1), "Platformers", true, 100);
+    stats.record_game(&[UserId(1)], &[UserId(1)]);
+
+    let reopened = Stats::open(Some(path));
+    assert_eq!(reopened.get_player(UserId(1)), stats.get_player(UserId(1)));
+    assert_eq!(reopened.get_player(UserId(1)).wins, 1);
+}
+
+#[test]
+fn guesses_are_only_written_when_saved() {
+    let path = env::temp_dir().join(format!(
+        "level-99-stats-batched-{}.json",
+        std::process::id()
+    ));
+    if path.exists() {
+        fs::remove_file(&path).unwrap();
+    }
+
+    let mut stats = Stats::open(Some(path.clone()));
+    stats.record_guess(UserId(1), "Platformers", true, 100);
+    stats.record_guess(UserId(1), "RPGs", false, -100);
+    assert!(!path.exists());
+
+    stats.save();
+    let reopened = Stats::open(Some(path));
+    assert_eq!(reopened.get_player(UserId(1)).correct_guesses, 1);
+    assert_eq!(reopened.get_player(UserId(1)).incorrect_guesses, 1);
+}
+
+#[test]
+fn rejudged_guesses_move_between_tallies() {
+    let mut stats = Stats::default();
+    stats.record_guess(UserId(1), "Platformers", false, -100);
+    stats.rejudge_guess(UserId(1), "Platformers", true, 200);
+
+    let player_stats = stats.get_player(UserId(1));
+    assert_eq!(player_stats.correct_guesses, 1);
+    assert_eq!(player_stats.incorrect_guesses, 0);
+    assert_eq!(player_stats.points, 100);
+    assert_eq!(
+        player_stats.get_favourite_categories(),
+        [("Platformers", 1)]
+    );
+
+    stats.rejudge_guess(UserId(1), "Platformers", false, -200);
+    assert!(stats.get_player(UserId(1)).categories.is_empty());
+}
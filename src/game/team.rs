@@ -2,10 +2,13 @@ use anyhow::*;
 use lazy_static::lazy_static;
 use parking_lot::RwLock;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use serenity::model::id::UserId;
 use std::collections::HashSet;
 use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use unidecode::unidecode;
 
 lazy_static! {
@@ -29,16 +32,69 @@ pub fn sanitize_name(name: &str) -> Result<String> {
     Ok(name)
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub enum TeamId {
     TeamName(String),
+    // A single player competing on their own in solo mode
+    Player(UserId),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ScoreReason {
+    CorrectGuess,
+    IncorrectGuess,
+    UnansweredChallenge,
+    // The quizmaster overruled the judgement of a guess
+    Rejudged,
+    // The quizmaster adjusted the score by hand
+    Adjusted,
+}
+
+impl ScoreReason {
+    fn describe(self) -> &'static str {
+        match self {
+            ScoreReason::CorrectGuess => "correct guess",
+            ScoreReason::IncorrectGuess => "incorrect guess",
+            ScoreReason::UnansweredChallenge => "unanswered challenge",
+            ScoreReason::Rejudged => "guess judged again",
+            ScoreReason::Adjusted => "adjusted by the quizmaster",
+        }
+    }
+}
+
+// The guess behind a score change, so that undoing the change also takes it out of the player's stats
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GuessRecord {
+    pub player: UserId,
+    pub category: String,
+    // Judgement of the guess once the change was made
+    pub is_correct: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ScoreChange {
+    pub team_id: TeamId,
+    pub delta: i32,
+    pub reason: ScoreReason,
+    // Answer of the question the change is about, if any
+    pub question: Option<String>,
+    // Milliseconds since the Unix epoch
+    pub timestamp: u64,
+    #[serde(default)]
+    pub guess: Option<GuessRecord>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Team {
     pub id: TeamId,
     pub players: HashSet<UserId>,
     pub score: i32,
+    // Every change that led to the current score, oldest first
+    #[serde(default)]
+    pub history: Vec<ScoreChange>,
+    // Name of the player competing on their own, if any
+    #[serde(default)]
+    pub player_name: Option<String>,
 }
 
 impl Team {
@@ -47,18 +103,100 @@ impl Team {
             id,
             score: 0,
             players: HashSet::new(),
+            history: Vec::new(),
+            player_name: None,
         }
     }
 
-    pub fn get_display_name(&self) -> &str {
+    pub fn get_display_name(&self) -> String {
         match &self.id {
-            TeamId::TeamName(name) => &name,
+            TeamId::TeamName(name) => name.clone(),
+            TeamId::Player(user_id) => self
+                .player_name
+                .clone()
+                .unwrap_or_else(|| format!("player-{}", user_id.0)),
         }
     }
 
-    pub fn update_score(&mut self, delta: i32) {
+    pub fn update_score(&mut self, delta: i32, reason: ScoreReason, question: Option<String>) {
+        self.update_score_for_guess(delta, reason, question, None);
+    }
+
+    pub fn update_score_for_guess(
+        &mut self,
+        delta: i32,
+        reason: ScoreReason,
+        question: Option<String>,
+        guess: Option<GuessRecord>,
+    ) {
         self.score += delta;
+        self.history.push(ScoreChange {
+            team_id: self.id.clone(),
+            delta,
+            reason,
+            question,
+            timestamp: next_timestamp(),
+            guess,
+        });
+    }
+
+    pub fn reset_score(&mut self) {
+        self.score = 0;
+        self.history.clear();
     }
 }
 
 pub type TeamsHandle = Arc<RwLock<Vec<Team>>>;
+
+static LAST_TIMESTAMP: AtomicU64 = AtomicU64::new(0);
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+// Strictly increasing, so that score changes made within the same millisecond can still be undone in order
+fn next_timestamp() -> u64 {
+    let now = now();
+    let previous = LAST_TIMESTAMP
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+            Some(now.max(last + 1))
+        })
+        .unwrap_or_default();
+    now.max(previous + 1)
+}
+
+// Reverts the most recent score change of any team, returning it
+pub fn undo_last_change(teams: &mut [Team]) -> Option<ScoreChange> {
+    let team = teams
+        .iter_mut()
+        .filter(|t| !t.history.is_empty())
+        .max_by_key(|t| t.history.last().map(|c| c.timestamp))?;
+    let change = team.history.pop()?;
+    team.score -= change.delta;
+    Some(change)
+}
+
+pub fn describe_score_history(name: &str, history: &[ScoreChange]) -> String {
+    if history.is_empty() {
+        return format!("The score of team {} has not changed yet", name);
+    }
+    let now = now();
+    let mut description = format!("Score history of team {}:", name);
+    for change in history {
+        let elapsed = Duration::from_millis(now.saturating_sub(change.timestamp)).as_secs();
+        description.push_str(&format!(
+            "\n- {}m{:02}s ago: {:+} points, {}",
+            elapsed / 60,
+            elapsed % 60,
+            change.delta,
+            change.reason.describe()
+        ));
+        if let Some(question) = &change.question {
+            description.push_str(&format!(" ({})", question));
+        }
+    }
+    description
+}
@@ -0,0 +1,135 @@
+use super::*;
+use crate::game::team::GuessRecord;
+use crate::output::mock::MockGameOutput;
+
+fn build_game() -> Game<MockGameOutput> {
+    let teams = Arc::new(RwLock::new(Vec::new()));
+    let mut game = Game::new(MockGameOutput::new(), teams, None, None);
+    game.join_team(UserId(1), "red").unwrap();
+    game.join_team(UserId(2), "blue").unwrap();
+    game
+}
+
+fn get_score(game: &Game<MockGameOutput>, team_name: &str) -> i32 {
+    let team_id = TeamId::TeamName(team_name.to_owned());
+    game.get_teams()
+        .iter()
+        .find(|t| t.id == team_id)
+        .unwrap()
+        .score
+}
+
+#[test]
+fn undo_reverts_the_latest_score_change() {
+    let mut game = build_game();
+    let red = TeamId::TeamName("red".to_owned());
+    let blue = TeamId::TeamName("blue".to_owned());
+    game.adjust_score(red.clone(), 300).unwrap();
+    game.adjust_score(blue.clone(), 200).unwrap();
+    game.adjust_score(red.clone(), -100).unwrap();
+    game.output.flush();
+
+    game.undo().unwrap();
+    assert_eq!(get_score(&game, "red"), 300);
+    assert_eq!(
+        game.output.flush(),
+        [Message::ScoreChangeUndone(red.clone(), -100, 300)]
+    );
+
+    game.undo().unwrap();
+    assert_eq!(get_score(&game, "blue"), 0);
+    assert_eq!(game.get_score_history(&red).unwrap().len(), 1);
+    assert!(game.get_score_history(&blue).unwrap().is_empty());
+}
+
+#[test]
+fn undo_reverts_guesses_and_their_stats() {
+    let mut game = build_game();
+    let red = TeamId::TeamName("red".to_owned());
+    game.adjust_score(red.clone(), 300).unwrap();
+    game.stats
+        .write()
+        .record_guess(UserId(1), "example category", true, 100);
+    game.teams.write()[0].update_score_for_guess(
+        100,
+        ScoreReason::CorrectGuess,
+        None,
+        Some(GuessRecord {
+            player: UserId(1),
+            category: "example category".to_owned(),
+            is_correct: true,
+        }),
+    );
+
+    game.undo().unwrap();
+    assert_eq!(get_score(&game, "red"), 300);
+    assert_eq!(game.get_player_stats(UserId(1)), PlayerStats::default());
+    assert_eq!(
+        game.get_score_history(&red).unwrap()[0].reason,
+        ScoreReason::Adjusted
+    );
+
+    game.undo().unwrap();
+    assert_eq!(get_score(&game, "red"), 0);
+    assert!(game.undo().is_err());
+}
+
+#[test]
+fn nothing_to_undo_after_reset() {
+    let mut game = build_game();
+    game.adjust_score(TeamId::TeamName("red".to_owned()), 300)
+        .unwrap();
+    game.reset_scores();
+    assert!(game.undo().is_err());
+    assert_eq!(get_score(&game, "red"), 0);
+}
+
+#[test]
+fn only_joined_players_are_solo_players() {
+    let mut game = build_game();
+    assert!(!game.is_solo_player(UserId(1)));
+
+    game.set_mode(GameMode::Solo).unwrap();
+    assert!(!game.is_solo_player(UserId(1)));
+    game.add_solo_player(UserId(1), "alice").unwrap();
+    assert!(game.is_solo_player(UserId(1)));
+    assert!(!game.is_solo_player(UserId(2)));
+}
+
+#[test]
+fn solo_players_are_shown_by_name() {
+    let mut game = build_game();
+    game.set_mode(GameMode::Solo).unwrap();
+    assert!(game.guess(UserId(1), "anything").is_err());
+    game.add_solo_player(UserId(1), "alice").unwrap();
+    let teams = game.get_teams();
+    assert_eq!(teams[0].get_display_name(), "alice");
+}
+
+#[test]
+fn failed_resume_keeps_current_teams() {
+    let snapshot_path = std::env::temp_dir().join(format!(
+        "level-99-failed-resume-{}.json",
+        std::process::id()
+    ));
+    Snapshot {
+        quiz_path: PathBuf::from("missing-quiz.csv"),
+        settings_overrides: SettingsOverrides::default(),
+        teams: vec![Team::new(TeamId::TeamName("green".to_owned()))],
+        initiative: None,
+        contributions: Vec::new(),
+        remaining_questions: Vec::new(),
+        final_results: None,
+        phase: quiz::snapshot::PhaseSnapshot::Cooldown,
+    }
+    .write(&snapshot_path)
+    .unwrap();
+
+    let mut game = build_game();
+    game.snapshot_path = Some(snapshot_path.clone());
+    let result = game.resume(|_teams| panic!("Channels should not change"));
+    assert!(result.is_err());
+    assert_eq!(get_score(&game, "red"), 0);
+    assert_eq!(game.get_teams().len(), 2);
+    Snapshot::delete(&snapshot_path).unwrap();
+}
@@ -1,6 +1,6 @@
-use anyhow::Result;
+use anyhow::*;
 use serenity::client::bridge::voice::ClientVoiceManager;
-use serenity::client::Context;
+use serenity::client::Context as SerenityContext;
 use serenity::prelude::{Mutex, TypeMapKey};
 use serenity::{
     client::{Client, EventHandler},
@@ -8,18 +8,27 @@ use serenity::{
     model::gateway::Ready,
 };
 use std::env;
+use std::path::Path;
 use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, Instant};
 
 mod channels;
 mod commands;
+mod config;
 mod game;
 mod output;
 mod preload;
+mod terminal;
 
+const OFFLINE_FLAG: &str = "--offline";
+
+use crate::config::Config;
 use crate::game::pool::Pool as GamePool;
+use crate::game::quiz::definition::validate;
 use crate::output::discord::DiscordOutput;
+use crate::output::recorder;
+use crate::output::terminal::AudioSink;
 
 struct VoiceManager;
 impl TypeMapKey for VoiceManager {
@@ -37,7 +46,7 @@ impl TypeMapKey for GamePool {
 
 struct Handler;
 impl EventHandler for Handler {
-    fn ready(&self, ctx: Context, ready: Ready) {
+    fn ready(&self, ctx: SerenityContext, ready: Ready) {
         println!("{} is connected!", ready.user.name);
         for guild in &ready.guilds {
             let guild_id = guild.id();
@@ -58,6 +67,56 @@ impl EventHandler for Handler {
 }
 
 fn main() -> Result<()> {
+    let config = Config::load()?;
+    preload::set_downloader(config.downloader.build()?);
+    preload::cache::set_eviction_policy(config.cache.clone());
+    preload::set_normalization(config.normalization.clone());
+
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let offline_flag = args.iter().position(|a| a == OFFLINE_FLAG);
+    if let Some(index) = offline_flag {
+        args.remove(index);
+    }
+    preload::set_offline(config.offline || offline_flag.is_some());
+
+    match args.first().map(String::as_str) {
+        None => run_discord(),
+        Some("terminal") => {
+            let audio_sink = match args.get(1).map(String::as_str) {
+                None => AudioSink::Local,
+                Some("--mute") => AudioSink::Null,
+                Some(other) => return Err(anyhow!("Unknown option: {}", other)),
+            };
+            terminal::run(audio_sink)
+        }
+        Some("validate") => {
+            let path = args.get(1).context("Usage: level-99 validate <quiz.csv>")?;
+            validate_quiz(Path::new(path))
+        }
+        Some("replay") => {
+            let path = args.get(1).context("Usage: level-99 replay <log.jsonl>")?;
+            recorder::replay(Path::new(path))
+        }
+        Some(other) => Err(anyhow!("Unknown mode: {}", other)),
+    }
+}
+
+fn validate_quiz(path: &Path) -> Result<()> {
+    let problems = validate::validate(path)
+        .with_context(|| format!("Could not open quiz with path {:?}", path))?;
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    match problems.len() {
+        0 => {
+            println!("No problems found in {:?}", path);
+            Ok(())
+        }
+        n => Err(anyhow!("Found {} problem(s) in {:?}", n, path)),
+    }
+}
+
+fn run_discord() -> Result<()> {
     // Create game pool
     let game_pool = Arc::new(GamePool::default());
     let game_pool_for_ticker = Arc::clone(&game_pool);
@@ -98,6 +157,7 @@ fn main() -> Result<()> {
             .configure(|c| c.prefix("!"))
             .group(&commands::quizmaster::MAIN_GROUP)
             .group(&commands::quizmaster::RESET_GROUP)
+            .group(&commands::quizmaster::CACHE_GROUP)
             .group(&commands::player::MAIN_GROUP),
     );
 
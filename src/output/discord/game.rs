@@ -1,6 +1,7 @@
 use anyhow::*;
 use parking_lot::RwLock;
 use serenity::model::id::{ChannelId, MessageId, UserId};
+use serenity::model::misc::Mentionable;
 use serenity::voice::LockedAudio;
 use std::collections::HashMap;
 use std::path::Path;
@@ -8,6 +9,7 @@ use std::sync::Arc;
 
 use crate::game::team::{TeamId, TeamsHandle};
 use crate::output::discord::GuildOutput;
+use crate::output::format::{Medium, MessageFormat};
 use crate::output::{AudioHandle, GameOutput, Message, Recipient};
 
 #[derive(Clone, Debug)]
@@ -23,89 +25,27 @@ impl DiscordGameOutput {
             teams,
         }
     }
+}
+
+impl MessageFormat for DiscordGameOutput {
+    fn get_medium(&self) -> Medium {
+        Medium::Discord
+    }
 
     fn get_team_display_name(&self, team_id: &TeamId) -> String {
+        if let TeamId::Player(user_id) = team_id {
+            return user_id.mention();
+        }
         self.teams
             .read()
             .iter()
             .find(|t| t.id == *team_id)
-            .and_then(|t| Some(t.get_display_name().to_owned()))
+            .and_then(|t| Some(t.get_display_name()))
             .unwrap_or("??".to_owned())
     }
 
-    fn interpret_message(&self, message: &Message) -> String {
-        use Message::*;
-        match message {
-            TeamScoreAdjusted(team_id, score) => {
-                let team_name = self.get_team_display_name(team_id);
-                format!("Team {}'s score was updated to {} points", team_name, score)
-            },
-            TeamsReset=> "Teams were reset".into(),
-            ScoresReset=> "Scores were reset".into(),
-            GamePaused=> "The game is now paused, use `!unpause` to resume.".into(),
-            GameUnpaused=> "The game has resumed.".into(),
-            GameEnded=> "The game was stopped.".into(),
-            QuizRules => "The quiz is about to begin!\n\n**📋 Rules**\n- For each song, your team can submit **one** guess using the `!guess something` command.\n- Guessing wrong will deduct the same amount of points you could have earned!\n- If you are not the first team to guess, point earned or deducted are halved.\n\n**🔥 Tips**\n- You can adjust the music volume by right clicking on the bot in the voice channel UI.\n- Sometimes it is wiser to not answer than to lose points!".into(),
-            PreloadFailed => "⚠️ The quiz contains some songs that could not be downloaded.".into(),
-            GuessCorrect(team_id, score_delta) => {
-                let team_name = self.get_team_display_name(team_id);
-                format!("✅ **Team {}** guessed correctly and earned {} points!",team_name, score_delta)
-            }
-            GuessIncorrect(team_id, score_delta) => {
-                let team_name = self.get_team_display_name(team_id);
-                format!("❌ **Team {}** guessed incorrectly and lost {} points. Womp womp 📯.",team_name, score_delta)
-            }
-            AnswerReveal(question) => format!("The answer was **{}**:\n{}", question.answer, question.url),
-            GuessesReveal(details) => {
-                let mut message = "This is what everyone guessed:".to_owned();
-                for (team_id, guess) in details {
-                    message += &format!("\n- **Team {}**: {}", self.get_team_display_name(team_id), guess);
-                }
-                message
-            },
-            ScoresRecap(teams) => {
-                let mut recap = "📈 Here are the scores so far:".to_owned();
-                for (index, (team_id, score)) in teams.iter().enumerate() {
-                    let rank = match index {
-                        0 => "🥇".to_owned(),
-                        1 => "🥈".to_owned(),
-                        2 => "🥉".to_owned(),
-                        _ => format!("#{}", index + 1),
-                    };
-                    recap += &format!(
-                        "\n{} **Team {}** with {} points",
-                        rank,
-                        self.get_team_display_name(team_id),
-                        score
-                    );
-                }
-                recap
-            }
-            TimeRemaining(duration) => format!("🕒 Only {} seconds left!", duration.as_secs()),
-            ChallengeSongBegins(category) => format!("🎧 Here is a song from the **{}** category! Your team **must** guess this one right or you will lose points.", category),
-            QuestionBegins(question) => format!("🎧 Here is a song from the **{}** category for {} points!", question.category, question.score_value),
-            TimeUp(question) => format!("⏰ Time's up! The answer was **{}**:\n{}", question.answer, question.url),
-            ChallengeSongTimeUp(team_id, amount) => format!("**Team {}** loses *{} points* for not answering the **CHALLENGE** question!", self.get_team_display_name(team_id), amount),
-            GameResults(team_id) => format!("🎊🎊 **TEAM {} WINS IT ALL!** 🎊🎊", self.get_team_display_name(team_id)).to_uppercase(),
-            VoteWait(team_id) => format!("⏳ **Team {}** is choosing a category for the next question.", self.get_team_display_name(team_id)),
-            VotePoll(options) => {
-                let mut message = "**🗳️ Choose a category**\nReact to this message to cast your vote for the next question's category!".to_owned();
-                for (reaction, category, value) in options {
-                    message += &format!("\n{} **{}** {}pts", reaction, category, value);
-                }
-                message
-            }  ,
-            WagerBegins(category) => format!("⚠️ A **CHALLENGE** question has appeared in the **{}** category!", category),
-            WagerWait => "⏳ Please wait while other teams are responding to the **CHALLENGE** question.".into(),
-            WagerRules(min, max) => format!("🍀 **Your team must answer this question**. Use the `!wager amount` command to wager between {} and {} points. This is the amount your team will earn or lose from this question.", min, max),
-            WagerResults(wagers) => {
-                let mut message = String::new();
-                for (team_id, amount) in wagers {
-                    message += &format!("**Team {}** is betting *{} points*!\n", self.get_team_display_name(team_id), amount);
-                }
-                message
-            }
-        }
+    fn get_player_display_name(&self, player: UserId) -> String {
+        player.mention()
     }
 }
 
@@ -123,6 +63,10 @@ impl AudioHandle for DiscordAudio {
     fn is_finished(&self) -> bool {
         self.locked_audio.lock().finished
     }
+
+    fn stop(&self) {
+        self.locked_audio.lock().pause();
+    }
 }
 
 impl GameOutput for DiscordGameOutput {
@@ -133,7 +77,7 @@ impl GameOutput for DiscordGameOutput {
         recipient: &Recipient,
         message: &Message,
     ) -> HashMap<TeamId, Result<(ChannelId, MessageId)>> {
-        let content = self.interpret_message(message);
+        let content = self.format_message(message);
         self.guild_output.read().say(recipient, &content)
     }
 
@@ -143,7 +87,7 @@ impl GameOutput for DiscordGameOutput {
         message: &Message,
         reactions: &Vec<String>,
     ) -> HashMap<TeamId, Result<(ChannelId, MessageId)>> {
-        let content = self.interpret_message(message);
+        let content = self.format_message(message);
         self.guild_output
             .read()
             .say_with_reactions(recipient, &content, reactions)
@@ -181,4 +125,8 @@ impl GameOutput for DiscordGameOutput {
     fn update_team_channels(&self, channel_ids: HashMap<TeamId, ChannelId>) {
         self.guild_output.write().update_team_channels(channel_ids)
     }
+
+    fn update_quizmaster_channel(&self, channel_id: ChannelId) {
+        self.guild_output.write().set_quizmaster_channel(channel_id)
+    }
 }
@@ -15,6 +15,7 @@ pub struct GuildOutput {
     guild_id: GuildId,
     discord_output: Arc<Mutex<DiscordOutput>>,
     team_channels: HashMap<TeamId, ChannelId>,
+    quizmaster_channel: Option<ChannelId>,
 }
 
 impl GuildOutput {
@@ -23,6 +24,22 @@ impl GuildOutput {
             guild_id,
             discord_output: Arc::clone(discord_output),
             team_channels: HashMap::new(),
+            quizmaster_channel: None,
+        }
+    }
+
+    pub fn set_quizmaster_channel(&mut self, channel_id: ChannelId) {
+        self.quizmaster_channel = Some(channel_id);
+    }
+
+    // Quizmaster messages are not sent to any team, so they are left out of the returned message IDs
+    fn say_to_quizmaster(&self, content: &str) {
+        let result = self
+            .quizmaster_channel
+            .context("Quizmaster has no channel")
+            .and_then(|c| self.discord_output.lock().say(c, content));
+        if let Err(e) = result {
+            eprintln!("Could not send quizmaster message: {:#}", e);
         }
     }
 
@@ -70,6 +87,7 @@ impl GuildOutput {
                     message_ids.extend(self.say(&Recipient::Team(team_id.clone()), content));
                 }
             }
+            Recipient::Quizmaster => self.say_to_quizmaster(content),
         }
         message_ids
     }
@@ -117,6 +135,7 @@ impl GuildOutput {
                     ));
                 }
             }
+            Recipient::Quizmaster => self.say_to_quizmaster(content),
         }
         message_ids
     }
@@ -0,0 +1,375 @@
+use serenity::model::id::UserId;
+
+use crate::game::quiz::definition::{Question, QuizMetadata};
+use crate::game::team::TeamId;
+use crate::output::{describe_time_estimate, Message};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Medium {
+    // Markdown and emoji are rendered, and votes are cast with reactions
+    Discord,
+    // Plain text, votes are cast with `!vote number`
+    Terminal,
+}
+
+// Wording of the messages sent to players, shared by every output so they only differ in decoration
+pub trait MessageFormat {
+    fn get_medium(&self) -> Medium;
+
+    fn get_team_display_name(&self, team_id: &TeamId) -> String;
+
+    fn get_player_display_name(&self, player: UserId) -> String;
+
+    fn bold(&self, text: &str) -> String {
+        match self.get_medium() {
+            Medium::Discord => format!("**{}**", text),
+            Medium::Terminal => text.to_owned(),
+        }
+    }
+
+    fn italic(&self, text: &str) -> String {
+        match self.get_medium() {
+            Medium::Discord => format!("*{}*", text),
+            Medium::Terminal => text.to_owned(),
+        }
+    }
+
+    // Emoji leading a message, followed by a space
+    fn emoji(&self, emoji: &str) -> String {
+        match self.get_medium() {
+            Medium::Discord => format!("{} ", emoji),
+            Medium::Terminal => String::new(),
+        }
+    }
+
+    // Emoji closing a message, preceded by a space
+    fn trailing_emoji(&self, emoji: &str) -> String {
+        match self.get_medium() {
+            Medium::Discord => format!(" {}", emoji),
+            Medium::Terminal => String::new(),
+        }
+    }
+
+    // Links in lists are not embedded, as Discord would preview every one of them
+    fn link(&self, url: &str) -> String {
+        match self.get_medium() {
+            Medium::Discord => format!("<{}>", url),
+            Medium::Terminal => url.to_owned(),
+        }
+    }
+
+    fn team(&self, team_id: &TeamId) -> String {
+        self.bold(&format!("Team {}", self.get_team_display_name(team_id)))
+    }
+
+    fn format_quiz_metadata(&self, metadata: &QuizMetadata) -> String {
+        let mut header = String::new();
+        if let Some(title) = &metadata.title {
+            header.push_str(&format!("{}\n", self.bold(&(self.emoji("🎶") + title))));
+        }
+        if let Some(author) = &metadata.author {
+            header.push_str(&format!("Created by {}\n", author));
+        }
+        if let Some(description) = &metadata.description {
+            match self.get_medium() {
+                Medium::Discord => header.push_str(&format!("_{}_\n", description)),
+                Medium::Terminal => header.push_str(&format!("{}\n", description)),
+            }
+        }
+        header
+    }
+
+    fn format_question_list(&self, header: &str, questions: &[(Question, String)]) -> String {
+        let mut list = header.to_owned();
+        for (question, reason) in questions {
+            list.push_str(&format!(
+                "\n- {} for {}: {} ({})\n  `{}`",
+                self.bold(&question.category),
+                question.score_value,
+                question.answer,
+                self.link(&question.url),
+                reason
+            ));
+        }
+        list
+    }
+
+    fn format_message(&self, message: &Message) -> String {
+        use Message::*;
+        match message {
+            TeamScoreAdjusted(team_id, score) => format!(
+                "Team {}'s score was updated to {} points",
+                self.get_team_display_name(team_id),
+                score
+            ),
+            TeamsReset => "Teams were reset".into(),
+            ScoresReset => "Scores were reset".into(),
+            GamePaused => "The game is now paused, use `!unpause` to resume.".into(),
+            GameUnpaused => "The game has resumed.".into(),
+            GameEnded => "The game was stopped.".into(),
+            QuizResumed => format!(
+                "{}The interrupted quiz was restored and will continue where it left off.",
+                self.emoji("♻️")
+            ),
+            QuizRules(metadata) => {
+                let mut rules = metadata
+                    .as_ref()
+                    .map(|m| self.format_quiz_metadata(m))
+                    .unwrap_or_default();
+                rules.push_str("The quiz is about to begin!\n\n");
+                rules.push_str(&self.bold(&(self.emoji("📋") + "Rules")));
+                rules.push_str(&format!("\n- For each song, your team can submit {} guess using the `!guess something` command.", self.bold("one")));
+                rules.push_str("\n- Guessing wrong will deduct the same amount of points you could have earned!");
+                rules.push_str("\n- If you are not the first team to guess, point earned or deducted are halved.");
+                rules.push_str("\n- Small typos are forgiven, and guesses that are very close get one more try.\n\n");
+                rules.push_str(&self.bold(&(self.emoji("🔥") + "Tips")));
+                if self.get_medium() == Medium::Discord {
+                    rules.push_str("\n- You can adjust the music volume by right clicking on the bot in the voice channel UI.");
+                }
+                rules.push_str("\n- Sometimes it is wiser to not answer than to lose points!");
+                rules
+            }
+            PreloadComplete(total) => format!("{}All {} songs are ready to play!", self.emoji("✅"), total),
+            PreloadFailed => format!("{}The quiz contains some songs that could not be downloaded. Waiting for the quizmaster to continue without them or to end the quiz.", self.emoji("⚠️")),
+            PreloadReport(failures) => {
+                let header = self.emoji("⚠️") + &self.bold("These questions could not be preloaded:");
+                let mut report = self.format_question_list(&header, failures);
+                report.push_str("\n\nUse `!continue` to play without these questions, or `!end` to stop the quiz.");
+                report
+            }
+            NormalizationReport(failures) => {
+                let header = self.emoji("🔊") + &self.bold("These songs could not be normalized and will play at their original volume:");
+                self.format_question_list(&header, failures)
+            }
+            PreloadProgress(completed, total, remaining) => {
+                let mut progress = format!(
+                    "{}Downloaded {} songs",
+                    self.emoji("⏳"),
+                    self.bold(&format!("{}/{}", completed, total))
+                );
+                if let Some(remaining) = remaining {
+                    progress.push_str(&format!(", {} remaining", describe_time_estimate(*remaining)));
+                }
+                progress
+            }
+            GuessAccepted(team_id, correction) => format!(
+                "{}The quizmaster accepted the guess of {}, their score was corrected by {:+} points.",
+                self.emoji("⚖️"),
+                self.team(team_id),
+                correction
+            ),
+            GuessRejected(team_id, correction) => format!(
+                "{}The quizmaster rejected the guess of {}, their score was corrected by {:+} points.",
+                self.emoji("⚖️"),
+                self.team(team_id),
+                correction
+            ),
+            GuessClose(guess) => format!(
+                "{}{} is very close! Check your spelling and guess again.",
+                self.emoji("🤏"),
+                self.bold(guess)
+            ),
+            GuessCorrect(team_id, score_delta) => format!(
+                "{}{} guessed correctly and earned {} points!",
+                self.emoji("✅"),
+                self.team(team_id),
+                score_delta
+            ),
+            GuessIncorrect(team_id, score_delta) => format!(
+                "{}{} guessed incorrectly and lost {} points. Womp womp{}.",
+                self.emoji("❌"),
+                self.team(team_id),
+                score_delta,
+                self.trailing_emoji("📯")
+            ),
+            MostValuablePlayer(user_id, points) => format!(
+                "{}{} is the MVP of this quiz with {}!",
+                self.emoji("🏅"),
+                self.get_player_display_name(*user_id),
+                self.bold(&format!("{} points", points))
+            ),
+            PlayerContributions(contributions) => {
+                let mut message = self.bold(&(self.emoji("📊") + "Points earned by each player"));
+                for (user_id, points) in contributions {
+                    message.push_str(&format!("\n- {}: {} points", self.get_player_display_name(*user_id), points));
+                }
+                message
+            }
+            AnswerReveal(question) => format!("The answer was {}:\n{}", self.bold(&question.answer), question.url),
+            GuessesReveal(details) => {
+                let mut message = "This is what everyone guessed:".to_owned();
+                for (team_id, guess) in details {
+                    message += &format!("\n- {}: {}", self.team(team_id), guess);
+                }
+                message
+            }
+            ScoreChangeUndone(team_id, delta, score) => format!(
+                "{}A change of {:+} points to {}'s score was undone, they now have {} points.",
+                self.emoji("↩️"),
+                delta,
+                self.team(team_id),
+                score
+            ),
+            ScoresRecap(teams) => {
+                let mut recap = format!("{}Here are the scores so far:", self.emoji("📈"));
+                for (index, (team_id, score)) in teams.iter().enumerate() {
+                    let rank = match (self.get_medium(), index) {
+                        (Medium::Discord, 0) => "🥇".to_owned(),
+                        (Medium::Discord, 1) => "🥈".to_owned(),
+                        (Medium::Discord, 2) => "🥉".to_owned(),
+                        _ => format!("#{}", index + 1),
+                    };
+                    recap += &format!("\n{} {} with {} points", rank, self.team(team_id), score);
+                }
+                recap
+            }
+            StealBegins(team_id, score_value, duration) => format!(
+                "{}{} guessed wrong! The other teams have {} seconds to steal this question for {} points with `!guess`.",
+                self.emoji("🚨"),
+                self.team(team_id),
+                duration.as_secs(),
+                score_value
+            ),
+            RevealStage(Some(length), score_value) => format!(
+                "{}Playing the first {} seconds of the song, a correct guess is worth {} points!",
+                self.emoji("🔊"),
+                length.as_secs(),
+                score_value
+            ),
+            RevealStage(None, score_value) => format!(
+                "{}Playing the full song, a correct guess is now worth {} points!",
+                self.emoji("🔊"),
+                score_value
+            ),
+            TimeRemaining(duration) => format!("{}Only {} seconds left!", self.emoji("🕒"), duration.as_secs()),
+            ChallengeSongBegins(category) => format!(
+                "{}Here is a song from the {} category! Your team {} guess this one right or you will lose points.",
+                self.emoji("🎧"),
+                self.bold(category),
+                self.bold("must")
+            ),
+            QuestionBegins(question) => format!(
+                "{}Here is a song from the {} category for {} points!",
+                self.emoji("🎧"),
+                self.bold(&question.category),
+                question.score_value
+            ),
+            TimeUp(question) => format!(
+                "{}Time's up! The answer was {}:\n{}",
+                self.emoji("⏰"),
+                self.bold(&question.answer),
+                question.url
+            ),
+            ChallengeSongTimeUp(team_id, amount) => format!(
+                "{} loses {} for not answering the {} question!",
+                self.team(team_id),
+                self.italic(&format!("{} points", amount)),
+                self.bold("CHALLENGE")
+            ),
+            FinalRoundBegins(category) => format!(
+                "{}{} Every team with points can now secretly wager up to its whole score on one last song from the {} category.",
+                self.emoji("🏁"),
+                self.bold("FINAL ROUND!"),
+                self.bold(category)
+            ),
+            FinalRoundSitOut => format!("{}Only teams with points can play the final round, please wait for the results.", self.emoji("⏳")),
+            FinalGuessRecorded => format!("{}Your answer was recorded, it will be revealed at the end of the quiz.", self.emoji("🤫")),
+            FinalRoundResults(question, results) => {
+                let mut message = format!(
+                    "{}The answer to the final question was {}:\n{}",
+                    self.emoji("🏁"),
+                    self.bold(&question.answer),
+                    question.url
+                );
+                for (team_id, guess, score_delta) in results {
+                    let guess = guess.as_ref().map(|g| format!("answered {}", g)).unwrap_or_else(|| "did not answer".to_owned());
+                    message += &format!("\n- {} {} ({:+} points)", self.team(team_id), guess, score_delta);
+                }
+                message
+            }
+            GameResults(team_id) => {
+                let winner = format!("TEAM {} WINS IT ALL!", self.get_team_display_name(team_id));
+                format!(
+                    "{}{}{}",
+                    self.emoji("🎊🎊"),
+                    self.bold(&winner),
+                    self.trailing_emoji("🎊🎊")
+                )
+                .to_uppercase()
+            }
+            GameTied(team_ids) => {
+                let names: Vec<String> = team_ids.iter().map(|t| format!("TEAM {}", self.get_team_display_name(t))).collect();
+                let tie = format!("IT'S A TIE BETWEEN {}!", names.join(" AND "));
+                format!(
+                    "{}{}{}",
+                    self.emoji("🤝"),
+                    self.bold(&tie),
+                    self.trailing_emoji("🤝")
+                )
+                .to_uppercase()
+            }
+            TiebreakerBegins(team_ids, category) => {
+                let names: Vec<String> = team_ids.iter().map(|t| self.team(t)).collect();
+                format!(
+                    "{}{} are tied for the win! Sudden death: the first of them to guess this song from the {} category wins.",
+                    self.emoji("⚔️"),
+                    names.join(", "),
+                    self.bold(category)
+                )
+            }
+            TiebreakerMiss(team_id) => format!("{}{} guessed wrong and is out of this tiebreaker.", self.emoji("❌"), self.team(team_id)),
+            VoteWait(team_id) => format!("{}{} is choosing a category for the next question.", self.emoji("⏳"), self.team(team_id)),
+            VotePoll(options) => {
+                let mut message = self.bold(&(self.emoji("🗳️") + "Choose a category"));
+                match self.get_medium() {
+                    Medium::Discord => message.push_str("\nReact to this message to cast your vote for the next question's category!"),
+                    Medium::Terminal => message.push_str("\nCast your vote for the next question's category with the `!vote number` command!"),
+                }
+                for (index, (reaction, category, value)) in options.iter().enumerate() {
+                    let label = match self.get_medium() {
+                        Medium::Discord => reaction.clone(),
+                        Medium::Terminal => format!("{}.", index + 1),
+                    };
+                    message += &format!("\n{} {} {}pts", label, self.bold(category), value);
+                }
+                message
+            }
+            WagerBegins(category) => format!(
+                "{}A {} question has appeared in the {} category!",
+                self.emoji("⚠️"),
+                self.bold("CHALLENGE"),
+                self.bold(category)
+            ),
+            WagerWait => format!(
+                "{}Please wait while other teams are responding to the {} question.",
+                self.emoji("⏳"),
+                self.bold("CHALLENGE")
+            ),
+            WagerRules(min, max) => format!(
+                "{}{}. Use the `!wager amount` command to wager between {} and {} points. This is the amount your team will earn or lose from this question.",
+                self.emoji("🍀"),
+                self.bold("Your team must answer this question"),
+                min,
+                max
+            ),
+            WagerResults(wagers) => {
+                let mut message = String::new();
+                for (team_id, amount) in wagers {
+                    message += &format!(
+                        "{} is betting {}!\n",
+                        self.team(team_id),
+                        self.italic(&format!("{} points", amount))
+                    );
+                }
+                // The terminal prints each message on its own line already
+                match self.get_medium() {
+                    Medium::Discord => message,
+                    Medium::Terminal => message.trim_end().to_owned(),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use super::*;
+
+struct Names {
+    medium: Medium,
+}
+
+impl MessageFormat for Names {
+    fn get_medium(&self) -> Medium {
+        self.medium
+    }
+
+    fn get_team_display_name(&self, team_id: &TeamId) -> String {
+        match team_id {
+            TeamId::TeamName(name) => name.clone(),
+            TeamId::Player(player) => self.get_player_display_name(*player),
+        }
+    }
+
+    fn get_player_display_name(&self, player: UserId) -> String {
+        format!("player #{}", player.0)
+    }
+}
+
+const DISCORD: Names = Names {
+    medium: Medium::Discord,
+};
+const TERMINAL: Names = Names {
+    medium: Medium::Terminal,
+};
+
+fn team(name: &str) -> TeamId {
+    TeamId::TeamName(name.to_owned())
+}
+
+#[test]
+fn only_discord_messages_are_decorated() {
+    let message = Message::GuessCorrect(team("red"), 100);
+    assert_eq!(
+        DISCORD.format_message(&message),
+        "✅ **Team red** guessed correctly and earned 100 points!"
+    );
+    assert_eq!(
+        TERMINAL.format_message(&message),
+        "Team red guessed correctly and earned 100 points!"
+    );
+}
+
+#[test]
+fn votes_match_how_they_are_cast() {
+    let message = Message::VotePoll(vec![("🍎".to_owned(), "Fruits".to_owned(), 200)]);
+    assert!(DISCORD
+        .format_message(&message)
+        .ends_with("\n🍎 **Fruits** 200pts"));
+    assert!(TERMINAL
+        .format_message(&message)
+        .ends_with("\n1. Fruits 200pts"));
+}
+
+#[test]
+fn winners_are_shouted() {
+    let message = Message::GameTied(vec![team("red"), team("blue")]);
+    assert_eq!(
+        TERMINAL.format_message(&message),
+        "IT'S A TIE BETWEEN TEAM RED AND TEAM BLUE!"
+    );
+}
+
+#[test]
+fn discord_keeps_closing_emoji() {
+    assert_eq!(
+        DISCORD.format_message(&Message::GameResults(team("red"))),
+        "🎊🎊 **TEAM RED WINS IT ALL!** 🎊🎊"
+    );
+    assert_eq!(
+        TERMINAL.format_message(&Message::GameResults(team("red"))),
+        "TEAM RED WINS IT ALL!"
+    );
+}
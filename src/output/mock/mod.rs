@@ -32,6 +32,8 @@ impl AudioHandle for MockAudio {
     fn is_finished(&self) -> bool {
         false
     }
+
+    fn stop(&self) {}
 }
 
 impl GameOutput for MockGameOutput {
@@ -77,4 +79,6 @@ impl GameOutput for MockGameOutput {
     }
 
     fn update_team_channels(&self, _channel_ids: HashMap<TeamId, ChannelId>) {}
+
+    fn update_quizmaster_channel(&self, _channel_id: ChannelId) {}
 }
@@ -1,40 +1,68 @@
 use anyhow::*;
+use serde::{Deserialize, Serialize};
 use serenity::model::id::{ChannelId, MessageId, UserId};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-use crate::game::quiz::definition::Question;
-use crate::game::team::TeamId;
+use crate::game::quiz::definition::{Question, QuizMetadata};
+use crate::game::team::{Team, TeamId};
 
 pub mod discord;
+pub mod format;
 #[cfg(test)]
 pub mod mock;
+pub mod recorder;
+pub mod terminal;
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum Recipient {
     AllTeams,
     Team(TeamId),
     AllTeamsExcept(TeamId),
+    Quizmaster,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum Message {
     AnswerReveal(Question),
     ChallengeSongBegins(String),
     ChallengeSongTimeUp(TeamId, i32),
+    FinalGuessRecorded,
+    FinalRoundBegins(String),
+    // What each team answered, if anything, and the points it won or lost
+    FinalRoundResults(Question, Vec<(TeamId, Option<String>, i32)>),
+    FinalRoundSitOut,
     GamePaused,
     GameResults(TeamId),
+    GameTied(Vec<TeamId>),
     GameUnpaused,
     GameEnded,
+    GuessAccepted(TeamId, i32),
+    GuessClose(String),
     GuessCorrect(TeamId, i32),
     GuessesReveal(Vec<(TeamId, String)>),
     GuessIncorrect(TeamId, i32),
-    QuizRules,
+    GuessRejected(TeamId, i32),
+    MostValuablePlayer(UserId, i32),
+    NormalizationReport(Vec<(Question, String)>),
+    PlayerContributions(Vec<(UserId, i32)>),
+    QuizResumed,
+    QuizRules(Option<QuizMetadata>),
+    PreloadComplete(usize),
     PreloadFailed,
+    PreloadProgress(usize, usize, Option<Duration>),
+    PreloadReport(Vec<(Question, String)>),
+    ScoreChangeUndone(TeamId, i32, i32),
     ScoresRecap(Vec<(TeamId, i32)>),
     ScoresReset,
     QuestionBegins(Question),
+    StealBegins(TeamId, u32, Duration),
+    // Length of the clip, or None for the full song, and how many points a correct guess is worth
+    RevealStage(Option<Duration>, u32),
     TeamScoreAdjusted(TeamId, i32),
+    TiebreakerBegins(Vec<TeamId>, String),
+    TiebreakerMiss(TeamId),
     TeamsReset,
     TimeRemaining(Duration),
     TimeUp(Question),
@@ -46,8 +74,54 @@ pub enum Message {
     WagerWait,
 }
 
+// Game events worth keeping for post-game review, in addition to the messages sent to players
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    QuizBegins {
+        quiz_path: PathBuf,
+        teams: Vec<Team>,
+    },
+    Message {
+        recipient: Recipient,
+        message: Message,
+    },
+    Guess {
+        team_id: TeamId,
+        player: UserId,
+        guess: String,
+        is_correct: bool,
+        score_delta: i32,
+    },
+    Wager {
+        team_id: TeamId,
+        amount: u32,
+    },
+    VoteResult {
+        category: String,
+        score_value: u32,
+    },
+    ScoreChange {
+        team_id: TeamId,
+        delta: i32,
+        score: i32,
+    },
+}
+
+// Rough wording for time estimates, which are not precise enough to be counted in seconds
+pub fn describe_time_estimate(duration: Duration) -> String {
+    match (duration.as_secs() + 30) / 60 {
+        0 => "less than a minute".to_owned(),
+        1 => "about a minute".to_owned(),
+        minutes => format!("about {} minutes", minutes),
+    }
+}
+
 pub trait AudioHandle {
     fn is_finished(&self) -> bool;
+
+    // Silences this audio only, unlike `GameOutput::stop_audio`
+    fn stop(&self);
 }
 
 pub trait GameOutput {
@@ -80,4 +154,9 @@ pub trait GameOutput {
     ) -> Result<Vec<UserId>>;
 
     fn update_team_channels(&self, channel_ids: HashMap<TeamId, ChannelId>);
+
+    fn update_quizmaster_channel(&self, channel_id: ChannelId);
+
+    // Only outputs that keep a record of the game care about events that are not messages
+    fn record(&self, _event: &Event) {}
 }
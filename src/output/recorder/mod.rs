@@ -0,0 +1,248 @@
+use anyhow::*;
+use directories_next::BaseDirs;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{ChannelId, MessageId, UserId};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::game::team::{Team, TeamId, TeamsHandle};
+use crate::output::terminal::{AudioSink, TerminalGameOutput};
+use crate::output::{Event, GameOutput, Message, Recipient};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LogEntry {
+    // Milliseconds since the Unix epoch
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+// Wraps another output and writes every event of each quiz to its own JSON-lines file
+#[derive(Clone)]
+pub struct Recorder<O> {
+    inner: O,
+    directory: Option<PathBuf>,
+    log: Arc<Mutex<Option<File>>>,
+}
+
+impl<O: GameOutput> Recorder<O> {
+    pub fn new(inner: O, directory: Option<PathBuf>) -> Self {
+        Recorder {
+            inner,
+            directory,
+            log: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    fn write(&self, event: &Event) {
+        let directory = match &self.directory {
+            Some(d) => d,
+            None => return,
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let mut log = self.log.lock();
+        let result = || -> Result<()> {
+            if let Event::QuizBegins { .. } = event {
+                fs::create_dir_all(directory)?;
+                let path = directory.join(format!("{}.jsonl", timestamp));
+                *log = Some(File::create(&path)?);
+            }
+            // Events happening outside of a quiz are not recorded
+            if let Some(file) = log.as_mut() {
+                let entry = LogEntry {
+                    timestamp,
+                    event: event.clone(),
+                };
+                writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+            }
+            Ok(())
+        }();
+        if let Err(e) = result {
+            eprintln!("Could not record game event: {:#}", e);
+        }
+    }
+}
+
+impl<O: GameOutput> GameOutput for Recorder<O> {
+    type Audio = O::Audio;
+
+    fn say(
+        &self,
+        recipient: &Recipient,
+        message: &Message,
+    ) -> HashMap<TeamId, Result<(ChannelId, MessageId)>> {
+        self.write(&Event::Message {
+            recipient: recipient.clone(),
+            message: message.clone(),
+        });
+        self.inner.say(recipient, message)
+    }
+
+    fn say_with_reactions(
+        &self,
+        recipient: &Recipient,
+        message: &Message,
+        reactions: &Vec<String>,
+    ) -> HashMap<TeamId, Result<(ChannelId, MessageId)>> {
+        self.write(&Event::Message {
+            recipient: recipient.clone(),
+            message: message.clone(),
+        });
+        self.inner.say_with_reactions(recipient, message, reactions)
+    }
+
+    fn play_youtube_audio(&self, url: String) -> Result<Self::Audio> {
+        self.inner.play_youtube_audio(url)
+    }
+
+    fn play_file_audio(&self, path: &Path) -> Result<Self::Audio> {
+        self.inner.play_file_audio(path)
+    }
+
+    fn stop_audio(&self) -> Result<()> {
+        self.inner.stop_audio()
+    }
+
+    fn read_reactions(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        reaction: String,
+    ) -> Result<Vec<UserId>> {
+        self.inner.read_reactions(channel_id, message_id, reaction)
+    }
+
+    fn update_team_channels(&self, channel_ids: HashMap<TeamId, ChannelId>) {
+        self.inner.update_team_channels(channel_ids)
+    }
+
+    fn update_quizmaster_channel(&self, channel_id: ChannelId) {
+        self.inner.update_quizmaster_channel(channel_id)
+    }
+
+    fn record(&self, event: &Event) {
+        self.write(event);
+        self.inner.record(event);
+    }
+}
+
+pub fn get_log_directory(name: &str) -> Result<PathBuf> {
+    let mut path = BaseDirs::new()
+        .context("could not locate system directories")?
+        .data_local_dir()
+        .to_path_buf();
+    path.push("level-99");
+    path.push("logs");
+    path.push(name);
+    Ok(path)
+}
+
+pub fn read_log(path: &Path) -> Result<Vec<LogEntry>> {
+    let file = File::open(path).with_context(|| format!("Could not open game log {:?}", path))?;
+    let mut entries = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line)
+            .with_context(|| format!("Invalid game log entry on line {}", index + 1))?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+fn format_elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn get_team_name(teams: &TeamsHandle, team_id: &TeamId) -> String {
+    teams
+        .read()
+        .iter()
+        .find(|t| t.id == *team_id)
+        .map(|t| t.get_display_name())
+        .unwrap_or_else(|| Team::new(team_id.clone()).get_display_name())
+}
+
+// Prints a recorded quiz the way the terminal mode would have displayed it, prefixed with the time into the quiz
+pub fn replay(path: &Path) -> Result<()> {
+    let entries = read_log(path)?;
+    let teams = Arc::new(RwLock::new(Vec::new()));
+    let output = TerminalGameOutput::new(teams.clone(), AudioSink::Null);
+    let start = entries.first().map(|e| e.timestamp).unwrap_or_default();
+
+    for entry in entries {
+        let elapsed = Duration::from_millis(entry.timestamp.saturating_sub(start));
+        print!("{} ", format_elapsed(elapsed));
+        match entry.event {
+            Event::QuizBegins {
+                quiz_path,
+                teams: quiz_teams,
+            } => {
+                let names: Vec<String> = quiz_teams.iter().map(|t| t.get_display_name()).collect();
+                println!(
+                    "Quiz {:?} begins with teams {}",
+                    quiz_path,
+                    names.join(", ")
+                );
+                *teams.write() = quiz_teams;
+            }
+            Event::Message { recipient, message } => {
+                output.say(&recipient, &message);
+            }
+            Event::Guess {
+                team_id,
+                player: _,
+                guess,
+                is_correct,
+                score_delta,
+            } => println!(
+                "Team {} guessed \"{}\" ({}, {:+} points)",
+                get_team_name(&teams, &team_id),
+                guess,
+                if is_correct { "correct" } else { "incorrect" },
+                score_delta
+            ),
+            Event::Wager { team_id, amount } => {
+                println!(
+                    "Team {} wagered {} points",
+                    get_team_name(&teams, &team_id),
+                    amount
+                )
+            }
+            Event::VoteResult {
+                category,
+                score_value,
+            } => println!("The vote picked {} for {} points", category, score_value),
+            Event::ScoreChange {
+                team_id,
+                delta,
+                score,
+            } => {
+                if let Some(team) = teams.write().iter_mut().find(|t| t.id == team_id) {
+                    team.score = score;
+                }
+                println!(
+                    "Team {} score changed by {:+} to {}",
+                    get_team_name(&teams, &team_id),
+                    delta,
+                    score
+                );
+            }
+        }
+    }
+    Ok(())
+}
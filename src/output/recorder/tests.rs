@@ -0,0 +1,101 @@
+use super::*;
+use std::env;
+
+use crate::game::quiz::definition::QuizMetadata;
+use crate::game::team::Team;
+use crate::output::mock::MockGameOutput;
+
+fn make_test_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("level-99-{}-{}", name, std::process::id()));
+    if dir.exists() {
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    dir
+}
+
+fn read_only_log(directory: &Path) -> Vec<LogEntry> {
+    let logs: Vec<PathBuf> = fs::read_dir(directory)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .collect();
+    assert_eq!(logs.len(), 1);
+    read_log(&logs[0]).unwrap()
+}
+
+#[test]
+fn records_quiz_events() {
+    let directory = make_test_dir("recorder");
+    let mut inner = MockGameOutput::new();
+    let recorder = Recorder::new(inner.clone(), Some(directory.clone()));
+    let red = TeamId::TeamName("red".to_owned());
+
+    // Nothing is recorded until a quiz begins
+    recorder.say(&Recipient::AllTeams, &Message::TeamsReset);
+    assert!(!directory.exists());
+
+    recorder.record(&Event::QuizBegins {
+        quiz_path: PathBuf::from("quiz.csv"),
+        teams: vec![Team::new(red.clone())],
+    });
+    recorder.say(
+        &Recipient::AllTeams,
+        &Message::QuizRules(Some(QuizMetadata::default())),
+    );
+    recorder.record(&Event::Guess {
+        team_id: red.clone(),
+        player: UserId(1),
+        guess: "answer".to_owned(),
+        is_correct: true,
+        score_delta: 100,
+    });
+    recorder.record(&Event::ScoreChange {
+        team_id: red.clone(),
+        delta: 100,
+        score: 100,
+    });
+
+    assert_eq!(
+        inner.flush(),
+        [
+            Message::TeamsReset,
+            Message::QuizRules(Some(QuizMetadata::default()))
+        ]
+    );
+
+    let entries = read_only_log(&directory);
+    assert_eq!(entries.len(), 4);
+    assert!(matches!(entries[0].event, Event::QuizBegins { .. }));
+    match &entries[1].event {
+        Event::Message { message, .. } => {
+            assert_eq!(*message, Message::QuizRules(Some(QuizMetadata::default())))
+        }
+        e => panic!("Unexpected event {:?}", e),
+    }
+    assert!(matches!(&entries[2].event, Event::Guess { guess, .. } if guess == "answer"));
+    assert!(matches!(
+        entries[3].event,
+        Event::ScoreChange { score: 100, .. }
+    ));
+    assert!(entries.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+
+    replay(
+        &fs::read_dir(&directory)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn does_not_record_without_directory() {
+    let recorder = Recorder::new(MockGameOutput::new(), None);
+    recorder.record(&Event::QuizBegins {
+        quiz_path: PathBuf::from("quiz.csv"),
+        teams: Vec::new(),
+    });
+    recorder.say(&Recipient::AllTeams, &Message::GameEnded);
+    assert!(recorder.log.lock().is_none());
+}
@@ -0,0 +1,257 @@
+use anyhow::*;
+use parking_lot::RwLock;
+use serenity::model::id::{ChannelId, MessageId, UserId};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::game::team::{TeamId, TeamsHandle};
+use crate::output::format::{Medium, MessageFormat};
+use crate::output::{AudioHandle, GameOutput, Message, Recipient};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AudioSink {
+    Local,
+    Null,
+}
+
+type AudioProcess = Arc<RwLock<Child>>;
+type Reactions = HashMap<(MessageId, String), HashSet<UserId>>;
+
+#[derive(Clone, Debug)]
+struct Poll {
+    message_id: MessageId,
+    reactions: Vec<String>,
+}
+
+#[derive(Clone)]
+pub struct TerminalGameOutput {
+    teams: TeamsHandle,
+    audio_sink: AudioSink,
+    playing: Arc<RwLock<Vec<AudioProcess>>>,
+    next_message_id: Arc<AtomicU64>,
+    last_poll: Arc<RwLock<Option<Poll>>>,
+    reactions: Arc<RwLock<Reactions>>,
+    player_names: Arc<RwLock<HashMap<UserId, String>>>,
+}
+
+impl TerminalGameOutput {
+    pub fn new(teams: TeamsHandle, audio_sink: AudioSink) -> Self {
+        TerminalGameOutput {
+            teams,
+            audio_sink,
+            playing: Arc::new(RwLock::new(Vec::new())),
+            next_message_id: Arc::new(AtomicU64::new(1)),
+            last_poll: Arc::new(RwLock::new(None)),
+            reactions: Arc::new(RwLock::new(HashMap::new())),
+            player_names: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn vote(&self, player: UserId, option: usize) -> Result<()> {
+        let poll = self
+            .last_poll
+            .read()
+            .clone()
+            .context("There is no vote in progress")?;
+        let reaction = option
+            .checked_sub(1)
+            .and_then(|index| poll.reactions.get(index))
+            .context("Invalid vote option")?;
+        self.reactions
+            .write()
+            .entry((poll.message_id, reaction.clone()))
+            .or_default()
+            .insert(player);
+        Ok(())
+    }
+
+    pub fn set_player_name(&self, player: UserId, name: &str) {
+        self.player_names.write().insert(player, name.to_owned());
+    }
+
+    fn get_recipient_display_name(&self, recipient: &Recipient) -> String {
+        match recipient {
+            Recipient::AllTeams => "everyone".to_owned(),
+            Recipient::Team(team_id) => format!("team {}", self.get_team_display_name(team_id)),
+            Recipient::AllTeamsExcept(team_id) => {
+                format!("everyone but team {}", self.get_team_display_name(team_id))
+            }
+            Recipient::Quizmaster => "quizmaster".to_owned(),
+        }
+    }
+
+    fn get_recipient_teams(&self, recipient: &Recipient) -> Vec<TeamId> {
+        let teams = self.teams.read();
+        match recipient {
+            Recipient::AllTeams => teams.iter().map(|t| t.id.clone()).collect(),
+            Recipient::Team(team_id) => vec![team_id.clone()],
+            Recipient::AllTeamsExcept(team_id) => teams
+                .iter()
+                .filter(|t| t.id != *team_id)
+                .map(|t| t.id.clone())
+                .collect(),
+            Recipient::Quizmaster => Vec::new(),
+        }
+    }
+
+    fn print(
+        &self,
+        recipient: &Recipient,
+        message: &Message,
+    ) -> HashMap<TeamId, Result<(ChannelId, MessageId)>> {
+        let message_id = MessageId(self.next_message_id.fetch_add(1, Ordering::SeqCst));
+        println!(
+            "[{}] {}",
+            self.get_recipient_display_name(recipient),
+            self.format_message(message)
+        );
+        self.get_recipient_teams(recipient)
+            .into_iter()
+            .map(|team_id| (team_id, Ok((ChannelId(0), message_id))))
+            .collect()
+    }
+
+    fn play(&self, child: Child) -> TerminalAudio {
+        let child = Arc::new(RwLock::new(child));
+        let mut playing = self.playing.write();
+        playing.retain(|c| matches!(c.write().try_wait(), Ok(None)));
+        playing.push(Arc::clone(&child));
+        TerminalAudio { child: Some(child) }
+    }
+}
+
+impl MessageFormat for TerminalGameOutput {
+    fn get_medium(&self) -> Medium {
+        Medium::Terminal
+    }
+
+    fn get_player_display_name(&self, player: UserId) -> String {
+        self.player_names
+            .read()
+            .get(&player)
+            .cloned()
+            .unwrap_or_else(|| format!("player #{}", player.0))
+    }
+
+    fn get_team_display_name(&self, team_id: &TeamId) -> String {
+        if let TeamId::Player(player) = team_id {
+            return self.get_player_display_name(*player);
+        }
+        self.teams
+            .read()
+            .iter()
+            .find(|t| t.id == *team_id)
+            .map(|t| t.get_display_name())
+            .unwrap_or_else(|| "??".to_owned())
+    }
+}
+
+pub struct TerminalAudio {
+    child: Option<AudioProcess>,
+}
+
+impl AudioHandle for TerminalAudio {
+    fn is_finished(&self) -> bool {
+        match &self.child {
+            None => true,
+            Some(child) => !matches!(child.write().try_wait(), Ok(None)),
+        }
+    }
+
+    fn stop(&self) {
+        if let Some(child) = &self.child {
+            let mut child = child.write();
+            child.kill().ok();
+            child.wait().ok();
+        }
+    }
+}
+
+impl GameOutput for TerminalGameOutput {
+    type Audio = TerminalAudio;
+
+    fn say(
+        &self,
+        recipient: &Recipient,
+        message: &Message,
+    ) -> HashMap<TeamId, Result<(ChannelId, MessageId)>> {
+        self.print(recipient, message)
+    }
+
+    fn say_with_reactions(
+        &self,
+        recipient: &Recipient,
+        message: &Message,
+        reactions: &Vec<String>,
+    ) -> HashMap<TeamId, Result<(ChannelId, MessageId)>> {
+        let message_ids = self.print(recipient, message);
+        if let Some(Ok((_channel_id, message_id))) = message_ids.values().next() {
+            *self.last_poll.write() = Some(Poll {
+                message_id: *message_id,
+                reactions: reactions.clone(),
+            });
+        }
+        message_ids
+    }
+
+    fn play_youtube_audio(&self, url: String) -> Result<TerminalAudio> {
+        if self.audio_sink == AudioSink::Null {
+            return Ok(TerminalAudio { child: None });
+        }
+        let mut download = Command::new("youtube-dl")
+            .args(["-q", "-o", "-", &url])
+            .stdout(Stdio::piped())
+            .spawn()
+            .context("Could not run youtube-dl")?;
+        let stream = download.stdout.take().context("Could not stream audio")?;
+        self.playing.write().push(Arc::new(RwLock::new(download)));
+        let player = Command::new("ffplay")
+            .args(["-nodisp", "-autoexit", "-loglevel", "quiet", "-i", "-"])
+            .stdin(stream)
+            .spawn()
+            .context("Could not run ffplay")?;
+        Ok(self.play(player))
+    }
+
+    fn play_file_audio(&self, path: &Path) -> Result<TerminalAudio> {
+        if self.audio_sink == AudioSink::Null {
+            return Ok(TerminalAudio { child: None });
+        }
+        let player = Command::new("ffplay")
+            .args(["-nodisp", "-autoexit", "-loglevel", "quiet"])
+            .arg(path)
+            .spawn()
+            .context("Could not run ffplay")?;
+        Ok(self.play(player))
+    }
+
+    fn stop_audio(&self) -> Result<()> {
+        for child in self.playing.write().drain(..) {
+            let mut child = child.write();
+            child.kill().ok();
+            child.wait().ok();
+        }
+        Ok(())
+    }
+
+    fn read_reactions(
+        &self,
+        _channel_id: ChannelId,
+        message_id: MessageId,
+        reaction: String,
+    ) -> Result<Vec<UserId>> {
+        Ok(self
+            .reactions
+            .read()
+            .get(&(message_id, reaction))
+            .map(|players| players.iter().copied().collect())
+            .unwrap_or_default())
+    }
+
+    fn update_team_channels(&self, _channel_ids: HashMap<TeamId, ChannelId>) {}
+
+    fn update_quizmaster_channel(&self, _channel_id: ChannelId) {}
+}
@@ -0,0 +1,381 @@
+use anyhow::*;
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::preload::{
+    get_cache_dir, preload_songs, url_to_video_id, PreloadHandle, PreloadState, Song,
+};
+
+const MANIFEST_FILE_NAME: &str = "manifest.json";
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+const BYTES_PER_MEGABYTE: u64 = 1024 * 1024;
+
+lazy_static! {
+    static ref MANIFEST: RwLock<Manifest> = RwLock::new(Manifest::load());
+    static ref EVICTION_POLICY: RwLock<EvictionPolicy> = RwLock::new(EvictionPolicy::default());
+    static ref IN_USE: RwLock<HashSet<PathBuf>> = RwLock::new(HashSet::new());
+}
+
+// Set when song plays were recorded since the manifest was last written
+static UNSAVED_PLAYS: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EvictionPolicy {
+    pub max_size_mb: Option<u64>,
+    pub max_age_days: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ManifestEntry {
+    pub url: Option<String>,
+    pub video_id: Option<String>,
+    pub path: PathBuf,
+    pub size: u64,
+    pub downloaded_at: SystemTime,
+    pub last_played_at: Option<SystemTime>,
+    // Volume adjustment applied to normalized copies, in dB
+    #[serde(default)]
+    pub gain: Option<f64>,
+}
+
+impl ManifestEntry {
+    fn new(url: Option<String>, path: &Path, now: SystemTime) -> Result<ManifestEntry> {
+        let size = fs::metadata(path)?.len();
+        let video_id = url.as_ref().and_then(|u| url_to_video_id(u).ok());
+        Ok(ManifestEntry {
+            url,
+            video_id,
+            path: path.to_path_buf(),
+            size,
+            downloaded_at: now,
+            last_played_at: None,
+            gain: None,
+        })
+    }
+
+    fn get_last_used(&self) -> SystemTime {
+        self.last_played_at.unwrap_or(self.downloaded_at)
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / BYTES_PER_MEGABYTE as f64)
+}
+
+fn format_age(time: SystemTime) -> String {
+    let age = SystemTime::now().duration_since(time).unwrap_or_default();
+    match age.as_secs() / SECONDS_PER_DAY {
+        0 => "today".to_owned(),
+        1 => "yesterday".to_owned(),
+        days => format!("{} days ago", days),
+    }
+}
+
+impl fmt::Display for ManifestEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let last_played = match self.last_played_at {
+            Some(time) => format_age(time),
+            None => "never".to_owned(),
+        };
+        write!(
+            f,
+            "{}: {}, downloaded {}, last played {}",
+            name,
+            format_size(self.size),
+            format_age(self.downloaded_at),
+            last_played
+        )?;
+        if let Some(gain) = self.gain {
+            write!(f, ", normalized by {:+.1} dB", gain)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Manifest {
+    entries: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    fn get_path() -> Result<PathBuf> {
+        Ok(get_cache_dir()?.join(MANIFEST_FILE_NAME))
+    }
+
+    fn load() -> Manifest {
+        let result = || -> Result<Manifest> {
+            let path = Manifest::get_path()?;
+            if path.exists() {
+                let content = fs::read_to_string(&path)?;
+                serde_json::from_str(&content).context("Could not parse cache manifest")
+            } else {
+                Manifest::scan(&get_cache_dir()?)
+            }
+        }();
+        result.unwrap_or_else(|e| {
+            eprintln!("Could not load cache manifest: {:#}", e);
+            Manifest::default()
+        })
+    }
+
+    // Adopts files downloaded before the manifest existed
+    fn scan(dir: &Path) -> Result<Manifest> {
+        let mut manifest = Manifest::default();
+        if !dir.exists() {
+            return Ok(manifest);
+        }
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let name = match path.file_name() {
+                Some(name) => name.to_string_lossy().into_owned(),
+                None => continue,
+            };
+            if !path.is_file() || name == MANIFEST_FILE_NAME || name.ends_with(".tmp") {
+                continue;
+            }
+            let modified = fs::metadata(&path)?
+                .modified()
+                .unwrap_or_else(|_| SystemTime::now());
+            let url = match name.split('.').next() {
+                Some(id) if !id.starts_with("local-") => {
+                    Some(format!("https://www.youtube.com/watch?v={}", id))
+                }
+                _ => None,
+            };
+            manifest
+                .entries
+                .push(ManifestEntry::new(url, &path, modified)?);
+        }
+        Ok(manifest)
+    }
+
+    fn save(&self) -> Result<()> {
+        UNSAVED_PLAYS.store(false, Ordering::SeqCst);
+        let path = Manifest::get_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&temp_path, &path)?;
+        Ok(())
+    }
+
+    pub fn get_entries(&self) -> &[ManifestEntry] {
+        &self.entries
+    }
+
+    pub fn get_total_size(&self) -> u64 {
+        self.entries.iter().map(|e| e.size).sum()
+    }
+
+    pub fn record_download(&mut self, url: &str, path: &Path, now: SystemTime) -> Result<()> {
+        let entry = ManifestEntry::new(Some(url.to_owned()), path, now)?;
+        self.entries.retain(|e| e.path != path);
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    pub fn record_normalization(
+        &mut self,
+        url: &str,
+        path: &Path,
+        gain: f64,
+        now: SystemTime,
+    ) -> Result<()> {
+        self.record_download(url, path, now)?;
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.path == path) {
+            entry.gain = Some(gain);
+        }
+        Ok(())
+    }
+
+    pub fn record_play(&mut self, path: &Path, now: SystemTime) -> bool {
+        match self.entries.iter_mut().find(|e| e.path == path) {
+            Some(entry) => {
+                entry.last_played_at = Some(now);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Removes entries that are too old, then the least recently used ones until the cache is small enough.
+    // Returns the removed entries so the caller can delete their files.
+    pub fn evict(
+        &mut self,
+        policy: &EvictionPolicy,
+        now: SystemTime,
+        protected: &HashSet<PathBuf>,
+    ) -> Vec<ManifestEntry> {
+        // Files deleted by hand are simply forgotten
+        self.entries.retain(|e| e.path.exists());
+        let mut kept = std::mem::take(&mut self.entries);
+        kept.sort_by_key(|e| std::cmp::Reverse(e.get_last_used()));
+
+        let mut evicted = Vec::new();
+        if let Some(max_age_days) = policy.max_age_days {
+            let max_age = Duration::from_secs(max_age_days * SECONDS_PER_DAY);
+            let (old, recent) = kept.into_iter().partition(|e| {
+                let age = now.duration_since(e.get_last_used()).unwrap_or_default();
+                age > max_age && !protected.contains(&e.path)
+            });
+            evicted = old;
+            kept = recent;
+        }
+
+        if let Some(max_size_mb) = policy.max_size_mb {
+            let max_size = max_size_mb * BYTES_PER_MEGABYTE;
+            let mut total_size: u64 = kept.iter().map(|e| e.size).sum();
+            let mut index = kept.len();
+            while total_size > max_size && index > 0 {
+                index -= 1;
+                if !protected.contains(&kept[index].path) {
+                    let entry = kept.remove(index);
+                    total_size -= entry.size;
+                    evicted.push(entry);
+                }
+            }
+        }
+
+        self.entries = kept;
+        evicted
+    }
+}
+
+pub fn set_eviction_policy(policy: EvictionPolicy) {
+    *EVICTION_POLICY.write() = policy;
+}
+
+// Files needed by quizzes preloaded since the bot started are never evicted
+pub fn add_in_use<I: IntoIterator<Item = PathBuf>>(paths: I) {
+    IN_USE.write().extend(paths);
+}
+
+pub fn record_download(url: &str, path: &Path) {
+    let mut manifest = MANIFEST.write();
+    let result = manifest
+        .record_download(url, path, SystemTime::now())
+        .and_then(|_| manifest.save());
+    if let Err(e) = result {
+        eprintln!("Could not record download in cache manifest: {:#}", e);
+    }
+}
+
+pub fn record_normalization(url: &str, path: &Path, gain: f64) {
+    let mut manifest = MANIFEST.write();
+    let result = manifest
+        .record_normalization(url, path, gain, SystemTime::now())
+        .and_then(|_| manifest.save());
+    if let Err(e) = result {
+        eprintln!("Could not record normalization in cache manifest: {:#}", e);
+    }
+}
+
+// Plays are only kept in memory until `save_plays` is called, so playing a song does not rewrite the manifest
+pub fn record_play(path: &Path) {
+    if MANIFEST.write().record_play(path, SystemTime::now()) {
+        UNSAVED_PLAYS.store(true, Ordering::SeqCst);
+    }
+}
+
+pub fn save_plays() {
+    if !UNSAVED_PLAYS.load(Ordering::SeqCst) {
+        return;
+    }
+    if let Err(e) = MANIFEST.read().save() {
+        eprintln!("Could not record song plays in cache manifest: {:#}", e);
+    }
+}
+
+pub fn list() -> Manifest {
+    let mut manifest = MANIFEST.read().clone();
+    manifest
+        .entries
+        .sort_by_key(|e| std::cmp::Reverse(e.get_last_used()));
+    manifest
+}
+
+pub fn prune() -> Result<Vec<ManifestEntry>> {
+    let policy = EVICTION_POLICY.read().clone();
+    let protected = IN_USE.read().clone();
+    let mut manifest = MANIFEST.write();
+    let evicted = manifest.evict(&policy, SystemTime::now(), &protected);
+    for entry in &evicted {
+        if let Err(e) = fs::remove_file(&entry.path) {
+            eprintln!("Could not delete cached file {:?}: {:#}", entry.path, e);
+        }
+    }
+    manifest.save()?;
+    Ok(evicted)
+}
+
+pub fn describe_pruned(evicted: &[ManifestEntry]) -> String {
+    let freed: u64 = evicted.iter().map(|e| e.size).sum();
+    format!(
+        "Removed {} cached song(s), freeing {}",
+        evicted.len(),
+        format_size(freed)
+    )
+}
+
+pub fn describe(manifest: &Manifest, max_entries: usize) -> String {
+    let mut description = format!(
+        "{} cached song(s), {} in total",
+        manifest.get_entries().len(),
+        format_size(manifest.get_total_size())
+    );
+    for entry in manifest.get_entries().iter().take(max_entries) {
+        description.push_str(&format!("\n- {}", entry));
+    }
+    if manifest.get_entries().len() > max_entries {
+        description.push_str(&format!(
+            "\n- and {} more",
+            manifest.get_entries().len() - max_entries
+        ));
+    }
+    description
+}
+
+// Downloads songs ahead of a quiz in the background, then reports which ones are ready
+pub fn warm<F>(songs: Vec<Song>, on_ready: F) -> Result<()>
+where
+    F: FnOnce(String) + Send + 'static,
+{
+    let mut handle = preload_songs(&songs)?;
+    thread::Builder::new()
+        .name("cache warm".to_owned())
+        .spawn(move || {
+            while let PreloadState::InProgress = handle.get_state() {
+                thread::sleep(Duration::from_millis(100));
+            }
+            on_ready(describe_warmed(&songs, &handle));
+        })?;
+    Ok(())
+}
+
+fn describe_warmed(songs: &[Song], handle: &PreloadHandle) -> String {
+    let failures = handle.get_failures();
+    let mut description = format!("{} song(s) are ready to play", songs.len() - failures.len());
+    for (song, reason) in failures {
+        description.push_str(&format!("\n- could not preload {}: {}", song.url, reason));
+    }
+    for (song, reason) in handle.get_normalization_failures() {
+        description.push_str(&format!("\n- could not normalize {}: {}", song.url, reason));
+    }
+    description
+}
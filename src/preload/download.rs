@@ -0,0 +1,135 @@
+use anyhow::*;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+
+use crate::preload::url_to_video_id;
+
+pub trait Downloader: Send + Sync {
+    // Downloads the audio of a video to `destination`, which does not include a file extension
+    fn download(&self, url: &str, destination: &Path) -> Result<()>;
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Backend {
+    #[default]
+    YoutubeDl,
+    YtDlp,
+    Fixtures,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DownloaderConfig {
+    pub backend: Backend,
+    pub binary: Option<PathBuf>,
+    pub arguments: Vec<String>,
+    pub fixture_directory: Option<PathBuf>,
+}
+
+impl DownloaderConfig {
+    pub fn build(&self) -> Result<Arc<dyn Downloader>> {
+        Ok(match self.backend {
+            Backend::YoutubeDl => Arc::new(YoutubeDl {
+                binary: self.binary.clone().unwrap_or_else(|| "youtube-dl".into()),
+                arguments: self.arguments.clone(),
+            }),
+            Backend::YtDlp => Arc::new(YtDlp {
+                binary: self.binary.clone().unwrap_or_else(|| "yt-dlp".into()),
+                arguments: self.arguments.clone(),
+            }),
+            Backend::Fixtures => {
+                Arc::new(Fixtures::new(self.fixture_directory.clone().context(
+                    "The fixtures downloader needs a `fixture_directory`",
+                )?))
+            }
+        })
+    }
+}
+
+fn run_command_line_downloader(
+    binary: &Path,
+    format: &str,
+    extra_arguments: &[String],
+    url: &str,
+    destination: &Path,
+) -> Result<()> {
+    let exit_status = Command::new(binary)
+        .args(["-f", format, "--no-playlist", "--ignore-config", "-o"])
+        .arg(destination)
+        .args(extra_arguments)
+        .arg(url)
+        .status()
+        .with_context(|| format!("Could not run {:?}", binary))?;
+    if !exit_status.success() {
+        return Err(anyhow!("{:?} exited with {}", binary, exit_status));
+    }
+    Ok(())
+}
+
+pub struct YoutubeDl {
+    binary: PathBuf,
+    arguments: Vec<String>,
+}
+
+impl Default for YoutubeDl {
+    fn default() -> Self {
+        YoutubeDl {
+            binary: "youtube-dl".into(),
+            arguments: Vec::new(),
+        }
+    }
+}
+
+impl Downloader for YoutubeDl {
+    fn download(&self, url: &str, destination: &Path) -> Result<()> {
+        run_command_line_downloader(
+            &self.binary,
+            "webm[abr>0]/bestaudio/best",
+            &self.arguments,
+            url,
+            destination,
+        )
+    }
+}
+
+pub struct YtDlp {
+    binary: PathBuf,
+    arguments: Vec<String>,
+}
+
+impl Downloader for YtDlp {
+    fn download(&self, url: &str, destination: &Path) -> Result<()> {
+        run_command_line_downloader(
+            &self.binary,
+            "bestaudio/best",
+            &self.arguments,
+            url,
+            destination,
+        )
+    }
+}
+
+// Copies audio files named after video IDs out of a directory, so quizzes can be preloaded without network access
+pub struct Fixtures {
+    directory: PathBuf,
+}
+
+impl Fixtures {
+    pub fn new(directory: PathBuf) -> Self {
+        Fixtures { directory }
+    }
+}
+
+impl Downloader for Fixtures {
+    fn download(&self, url: &str, destination: &Path) -> Result<()> {
+        let id = url_to_video_id(url)?;
+        let source = self.directory.join(id);
+        fs::copy(&source, destination)
+            .with_context(|| format!("Could not copy fixture {:?}", source))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,441 @@
+use anyhow::*;
+use directories_next::BaseDirs;
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+pub mod cache;
+pub mod download;
+pub mod normalize;
+pub mod youtube;
+
+#[cfg(test)]
+mod tests;
+
+use self::download::{Downloader, YoutubeDl};
+use self::normalize::NormalizationConfig;
+use self::youtube::YoutubeUrl;
+
+const LOCAL_URL_PREFIX: &str = "file://";
+
+// A song to play during the quiz, optionally restricted to an excerpt of the video
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Song {
+    pub url: String,
+    pub start: Option<Duration>,
+    pub end: Option<Duration>,
+}
+
+impl Song {
+    fn get_start(&self) -> Duration {
+        self.start
+            .unwrap_or_else(|| url_to_start_time(&self.url).unwrap_or_default())
+    }
+
+    fn is_excerpt(&self) -> bool {
+        self.get_start() > Duration::default() || self.end.is_some()
+    }
+}
+
+#[derive(Clone)]
+pub struct CacheEntry {
+    pub path: PathBuf,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SongState {
+    InProgress,
+    Success,
+    // Playable, but at its original volume
+    Unnormalized(String),
+    Failure(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct PreloadHandle {
+    states: Arc<RwLock<HashMap<Song, SongState>>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum PreloadState {
+    InProgress,
+    Success,
+    Failure,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PreloadProgress {
+    pub completed: usize,
+    pub total: usize,
+}
+
+impl PreloadProgress {
+    // Assumes the remaining songs take as long to preload as the ones completed so far
+    pub fn estimate_remaining_time(&self, elapsed: Duration) -> Option<Duration> {
+        if self.completed == 0 {
+            return None;
+        }
+        let remaining = self.total.saturating_sub(self.completed);
+        Some(elapsed.mul_f64(remaining as f64 / self.completed as f64))
+    }
+}
+
+impl PreloadHandle {
+    pub fn get_state(&mut self) -> PreloadState {
+        let states = self.states.read();
+        if states.values().any(|s| *s == SongState::InProgress) {
+            PreloadState::InProgress
+        } else if states.values().any(|s| matches!(s, SongState::Failure(_))) {
+            PreloadState::Failure
+        } else {
+            PreloadState::Success
+        }
+    }
+
+    pub fn get_progress(&self) -> PreloadProgress {
+        let states = self.states.read();
+        PreloadProgress {
+            completed: states
+                .values()
+                .filter(|s| **s != SongState::InProgress)
+                .count(),
+            total: states.len(),
+        }
+    }
+
+    pub fn get_failures(&self) -> Vec<(Song, String)> {
+        self.states
+            .read()
+            .iter()
+            .filter_map(|(song, state)| match state {
+                SongState::Failure(reason) => Some((song.clone(), reason.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn get_normalization_failures(&self) -> Vec<(Song, String)> {
+        self.states
+            .read()
+            .iter()
+            .filter_map(|(song, state)| match state {
+                SongState::Unnormalized(reason) => Some((song.clone(), reason.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+lazy_static! {
+    static ref DOWNLOADER: RwLock<Arc<dyn Downloader>> =
+        RwLock::new(Arc::new(YoutubeDl::default()));
+}
+
+lazy_static! {
+    static ref NORMALIZATION: RwLock<NormalizationConfig> =
+        RwLock::new(NormalizationConfig::default());
+    static ref OFFLINE: RwLock<bool> = RwLock::new(false);
+}
+
+pub fn set_downloader(downloader: Arc<dyn Downloader>) {
+    *DOWNLOADER.write() = downloader;
+}
+
+pub fn set_normalization(config: NormalizationConfig) {
+    *NORMALIZATION.write() = config;
+}
+
+// In offline mode, songs are only ever played from the cache and nothing is downloaded
+pub fn set_offline(offline: bool) {
+    *OFFLINE.write() = offline;
+}
+
+pub fn is_offline() -> bool {
+    *OFFLINE.read()
+}
+
+pub fn get_cache_dir() -> Result<PathBuf> {
+    let mut dir = BaseDirs::new()
+        .context("could not locate system directories")?
+        .cache_dir()
+        .to_path_buf();
+    dir.push("level-99");
+    Ok(dir)
+}
+
+// Local files are always played from `file://` URLs once a quiz is loaded
+pub fn is_local(url: &str) -> bool {
+    url.starts_with(LOCAL_URL_PREFIX)
+}
+
+// Quiz files can also point to audio files by their path, as long as the file exists.
+// Anything else is left to the Youtube parser, since links are often written without a scheme.
+pub fn is_local_path(url: &str, base_dir: &Path) -> bool {
+    is_local(url) || (!url.contains("://") && base_dir.join(url).is_file())
+}
+
+// Turns a `file://` URL or a path relative to `base_dir` into an absolute `file://` URL
+pub fn resolve_local_url(url: &str, base_dir: &Path) -> String {
+    let path = base_dir.join(local_url_to_path(url));
+    format!("{}{}", LOCAL_URL_PREFIX, path.to_string_lossy())
+}
+
+pub fn local_url_to_path(url: &str) -> PathBuf {
+    PathBuf::from(url.strip_prefix(LOCAL_URL_PREFIX).unwrap_or(url))
+}
+
+// Where the full audio of a song can be found once it is downloaded
+fn url_to_source_path(cache_dir: &Path, url: &str) -> Result<PathBuf> {
+    if is_local(url) {
+        Ok(local_url_to_path(url))
+    } else {
+        url_to_cache_path(cache_dir, url)
+    }
+}
+
+pub fn url_to_video_id(url: &str) -> Result<String> {
+    YoutubeUrl::parse(url).map(|u| u.video_id)
+}
+
+pub fn url_to_path(url: &str) -> Result<PathBuf> {
+    url_to_cache_path(&get_cache_dir()?, url)
+}
+
+fn url_to_cache_path(cache_dir: &Path, url: &str) -> Result<PathBuf> {
+    Ok(cache_dir.join(url_to_video_id(url)?))
+}
+
+fn url_to_start_time(url: &str) -> Result<Duration> {
+    Ok(YoutubeUrl::parse(url)?.start.unwrap_or_default())
+}
+
+fn local_cache_name(path: &Path) -> String {
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    format!("local-{:x}", hasher.finish())
+}
+
+// Normalized copies are named after the target loudness, so changing it does not play stale copies
+fn song_to_normalized_path(
+    cache_dir: &Path,
+    song: &Song,
+    config: &NormalizationConfig,
+) -> Result<PathBuf> {
+    let path = song_to_path(cache_dir, song)?;
+    let name = if is_local(&song.url) && !song.is_excerpt() {
+        local_cache_name(&path)
+    } else {
+        path.file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    };
+    let file_name = format!("{}.{}lufs", name, config.target_lufs);
+    Ok(cache_dir.join(file_name))
+}
+
+fn song_to_path(cache_dir: &Path, song: &Song) -> Result<PathBuf> {
+    let source_path = url_to_source_path(cache_dir, &song.url)?;
+    if !song.is_excerpt() {
+        return Ok(source_path);
+    }
+    let end = match song.end {
+        Some(end) => end.as_secs().to_string(),
+        None => "end".to_owned(),
+    };
+    // Excerpts of local files are cached under a name derived from their full path
+    let (cache_dir, name) = if is_local(&song.url) {
+        (cache_dir.to_path_buf(), local_cache_name(&source_path))
+    } else {
+        let name = source_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        (source_path.with_file_name(""), name)
+    };
+    let file_name = format!("{}.{}-{}", name, song.get_start().as_secs(), end);
+    Ok(cache_dir.join(file_name))
+}
+
+fn download(downloader: &dyn Downloader, cache_dir: &Path, url: &str, offline: bool) -> Result<()> {
+    let destination = url_to_cache_path(cache_dir, url)?;
+    if destination.exists() {
+        return Ok(());
+    }
+    if offline {
+        return Err(anyhow!("Song is not in the cache and offline mode is on"));
+    }
+    downloader
+        .download(url, &destination)
+        .context("Could not download song")?;
+    cache::record_download(url, &destination);
+    Ok(())
+}
+
+// Re-encodes the requested segment so playback starts and stops exactly on the excerpt boundaries
+fn trim(source: &Path, destination: &Path, start: Duration, end: Option<Duration>) -> Result<()> {
+    let start_arg = start.as_secs_f64().to_string();
+    let mut args = vec![
+        "-y".to_owned(),
+        "-loglevel".to_owned(),
+        "error".to_owned(),
+        "-ss".to_owned(),
+        start_arg,
+        "-i".to_owned(),
+        source.to_string_lossy().into_owned(),
+        "-vn".to_owned(),
+    ];
+    if let Some(end) = end {
+        let length = end
+            .checked_sub(start)
+            .context("Excerpt ends before it starts")?;
+        args.push("-t".to_owned());
+        args.push(length.as_secs_f64().to_string());
+    }
+    args.extend(vec!["-f".to_owned(), "matroska".to_owned()]);
+    args.push(destination.to_string_lossy().into_owned());
+
+    let exit_status = Command::new("ffmpeg").args(&args).status()?;
+    if !exit_status.success() {
+        return Err(anyhow!("ffmpeg exited with {}", exit_status));
+    }
+    Ok(())
+}
+
+fn preload_song(
+    downloader: &dyn Downloader,
+    cache_dir: &Path,
+    normalization: &NormalizationConfig,
+    offline: bool,
+    download_failures: &mut HashMap<String, String>,
+    song: &Song,
+) -> Result<SongState> {
+    let normalized_path = song_to_normalized_path(cache_dir, song, normalization)?;
+    if normalization.enabled && normalized_path.exists() {
+        return Ok(SongState::Success);
+    }
+
+    if is_local(&song.url) {
+        let path = local_url_to_path(&song.url);
+        if !path.is_file() {
+            return Err(anyhow!("Audio file {:?} does not exist", path));
+        }
+    } else if !song_to_path(cache_dir, song)?.exists() {
+        // Excerpts that were already trimmed do not need the full song anymore,
+        // and songs sharing a URL only attempt the download once
+        if let Some(reason) = download_failures.get(&song.url) {
+            return Err(anyhow!("{}", reason));
+        }
+        fs::create_dir_all(cache_dir)?;
+        if let Err(e) = download(downloader, cache_dir, &song.url, offline) {
+            download_failures.insert(song.url.clone(), format!("{:#}", e));
+            return Err(e);
+        }
+    }
+
+    if song.is_excerpt() {
+        fs::create_dir_all(cache_dir)?;
+        let source = url_to_source_path(cache_dir, &song.url)?;
+        let destination = song_to_path(cache_dir, song)?;
+        if !destination.exists() {
+            trim(&source, &destination, song.get_start(), song.end)
+                .context("Could not trim song")?;
+            cache::record_download(&song.url, &destination);
+        }
+    }
+
+    // Songs that cannot be normalized are still playable, only louder or quieter than the others
+    if normalization.enabled {
+        fs::create_dir_all(cache_dir)?;
+        let source = song_to_path(cache_dir, song)?;
+        match normalize::normalize(normalization, &source, &normalized_path) {
+            Ok(gain) => cache::record_normalization(&song.url, &normalized_path, gain),
+            Err(e) => return Ok(SongState::Unnormalized(format!("{:#}", e))),
+        }
+    }
+    Ok(SongState::Success)
+}
+
+pub fn preload_songs(songs: &[Song]) -> Result<PreloadHandle> {
+    let normalization = NORMALIZATION.read().clone();
+    let offline = is_offline();
+    let cache_dir = get_cache_dir()?;
+    cache::add_in_use(
+        songs
+            .iter()
+            .filter_map(|s| song_to_path(&cache_dir, s).ok()),
+    );
+    cache::add_in_use(
+        songs
+            .iter()
+            .filter_map(|s| song_to_normalized_path(&cache_dir, s, &normalization).ok()),
+    );
+
+    let states: HashMap<Song, SongState> = songs
+        .iter()
+        .map(|s| (s.clone(), SongState::InProgress))
+        .collect();
+    let states = Arc::new(RwLock::new(states));
+    let thread_states = states.clone();
+    let songs = songs.to_vec();
+    thread::Builder::new()
+        .name("preload".to_owned())
+        .spawn(move || {
+            let downloader = DOWNLOADER.read().clone();
+            let mut download_failures = HashMap::new();
+            for song in &songs {
+                let result = preload_song(
+                    downloader.as_ref(),
+                    &cache_dir,
+                    &normalization,
+                    offline,
+                    &mut download_failures,
+                    song,
+                );
+                let state = match result {
+                    Ok(state) => state,
+                    Err(e) => {
+                        let reason = format!("{:#}", e);
+                        eprintln!("Could not preload song {}: {}", song.url, reason);
+                        SongState::Failure(reason)
+                    }
+                };
+                thread_states.write().insert(song.clone(), state);
+            }
+            if let Err(e) = cache::prune() {
+                eprintln!("Could not prune song cache: {:#}", e);
+            }
+        })?;
+
+    Ok(PreloadHandle { states })
+}
+
+pub fn retrieve_song(song: &Song) -> Option<CacheEntry> {
+    let cache_dir = get_cache_dir().ok()?;
+    let normalization = NORMALIZATION.read().clone();
+    if normalization.enabled {
+        if let Ok(path) = song_to_normalized_path(&cache_dir, song, &normalization) {
+            if path.exists() {
+                cache::record_play(&path);
+                return Some(CacheEntry { path });
+            }
+        }
+    }
+    if let Ok(path) = song_to_path(&cache_dir, song) {
+        if path.exists() {
+            cache::record_play(&path);
+            return Some(CacheEntry { path });
+        }
+    }
+    eprintln!("Preload song cache miss: {}", song.url);
+    None
+}
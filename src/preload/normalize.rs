@@ -0,0 +1,85 @@
+use anyhow::*;
+use serde::Deserialize;
+use std::path::Path;
+use std::process::Command;
+
+const DEFAULT_TARGET_LUFS: f64 = -16.0;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NormalizationConfig {
+    pub enabled: bool,
+    pub target_lufs: f64,
+}
+
+impl Default for NormalizationConfig {
+    fn default() -> Self {
+        NormalizationConfig {
+            enabled: false,
+            target_lufs: DEFAULT_TARGET_LUFS,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LoudnessMeasurement {
+    input_i: String,
+}
+
+// Extracts the integrated loudness from the JSON block ffmpeg's loudnorm filter prints at the end of its output
+pub fn parse_loudness(ffmpeg_output: &str) -> Result<f64> {
+    let start = ffmpeg_output
+        .rfind('{')
+        .context("No loudness measurement in ffmpeg output")?;
+    let end = ffmpeg_output[start..]
+        .find('}')
+        .context("Incomplete loudness measurement in ffmpeg output")?;
+    let measurement: LoudnessMeasurement =
+        serde_json::from_str(&ffmpeg_output[start..=start + end])?;
+    let loudness: f64 = measurement
+        .input_i
+        .parse()
+        .with_context(|| format!("Invalid loudness measurement {}", measurement.input_i))?;
+    if !loudness.is_finite() {
+        return Err(anyhow!("Song is silent"));
+    }
+    Ok(loudness)
+}
+
+// Measures the integrated loudness of a file, according to EBU R128
+fn measure_loudness(source: &Path) -> Result<f64> {
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-nostats", "-i"])
+        .arg(source)
+        .args([
+            "-vn",
+            "-af",
+            "loudnorm=print_format=json",
+            "-f",
+            "null",
+            "-",
+        ])
+        .output()
+        .context("Could not run ffmpeg")?;
+    if !output.status.success() {
+        return Err(anyhow!("ffmpeg exited with {}", output.status));
+    }
+    parse_loudness(&String::from_utf8_lossy(&output.stderr))
+}
+
+// Writes a copy of `source` to `destination` adjusted to the target loudness, and returns the gain that was applied in dB
+pub fn normalize(config: &NormalizationConfig, source: &Path, destination: &Path) -> Result<f64> {
+    let gain = config.target_lufs - measure_loudness(source)?;
+    let exit_status = Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "error", "-i"])
+        .arg(source)
+        .args(["-vn", "-af", &format!("volume={:.2}dB", gain)])
+        .args(["-f", "matroska"])
+        .arg(destination)
+        .status()
+        .context("Could not run ffmpeg")?;
+    if !exit_status.success() {
+        return Err(anyhow!("ffmpeg exited with {}", exit_status));
+    }
+    Ok(gain)
+}
@@ -0,0 +1,322 @@
+use super::*;
+use std::collections::HashSet;
+use std::env;
+use std::time::SystemTime;
+
+use crate::preload::cache::{EvictionPolicy, Manifest};
+use crate::preload::download::{DownloaderConfig, Fixtures};
+
+fn make_test_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("level-99-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn extracts_video_id() {
+    let id = url_to_video_id("https://www.youtube.com/watch?v=abc123&t=30").unwrap();
+    assert_eq!(id, "abc123");
+    assert!(url_to_video_id("https://example.com/song").is_err());
+}
+
+#[test]
+fn fixtures_copy_files_named_after_video_id() {
+    let fixture_dir = make_test_dir("fixtures");
+    let cache_dir = make_test_dir("fixtures-cache");
+    fs::write(fixture_dir.join("abc123"), "audio").unwrap();
+
+    let downloader = Fixtures::new(fixture_dir);
+    let destination = cache_dir.join("abc123");
+    downloader
+        .download("https://www.youtube.com/watch?v=abc123", &destination)
+        .unwrap();
+    assert_eq!(fs::read_to_string(&destination).unwrap(), "audio");
+
+    let missing = downloader.download(
+        "https://www.youtube.com/watch?v=zzz",
+        &cache_dir.join("zzz"),
+    );
+    assert!(missing.is_err());
+}
+
+#[test]
+fn fixtures_backend_requires_directory() {
+    let config: DownloaderConfig = toml::from_str("backend = \"fixtures\"").unwrap();
+    assert!(config.build().is_err());
+
+    let config: DownloaderConfig =
+        toml::from_str("backend = \"yt-dlp\"\narguments = [\"--cookies\", \"cookies.txt\"]")
+            .unwrap();
+    assert!(config.build().is_ok());
+}
+
+fn make_manifest(dir: &Path, sizes_and_ages: &[(usize, u64)], now: SystemTime) -> Manifest {
+    let mut manifest = Manifest::default();
+    for (index, (size, age_days)) in sizes_and_ages.iter().enumerate() {
+        let path = dir.join(format!("song-{}", index));
+        fs::write(&path, vec![0u8; *size]).unwrap();
+        let downloaded_at = now - Duration::from_secs(age_days * 24 * 60 * 60);
+        let url = format!("https://www.youtube.com/watch?v=song-{}", index);
+        manifest
+            .record_download(&url, &path, downloaded_at)
+            .unwrap();
+    }
+    manifest
+}
+
+#[test]
+fn evicts_entries_older_than_max_age() {
+    let dir = make_test_dir("evict-age");
+    let now = SystemTime::now();
+    let mut manifest = make_manifest(&dir, &[(10, 1), (10, 40), (10, 5)], now);
+    let policy = EvictionPolicy {
+        max_size_mb: None,
+        max_age_days: Some(30),
+    };
+    let evicted = manifest.evict(&policy, now, &HashSet::new());
+    assert_eq!(evicted.len(), 1);
+    assert_eq!(evicted[0].path, dir.join("song-1"));
+    assert_eq!(manifest.get_entries().len(), 2);
+}
+
+#[test]
+fn evicts_least_recently_used_entries_over_max_size() {
+    let dir = make_test_dir("evict-size");
+    let now = SystemTime::now();
+    let megabyte = 1024 * 1024;
+    let mut manifest = make_manifest(&dir, &[(megabyte, 3), (megabyte, 1), (megabyte, 2)], now);
+    manifest.record_play(&dir.join("song-0"), now);
+    let policy = EvictionPolicy {
+        max_size_mb: Some(2),
+        max_age_days: None,
+    };
+    let evicted = manifest.evict(&policy, now, &HashSet::new());
+    assert_eq!(evicted.len(), 1);
+    assert_eq!(evicted[0].path, dir.join("song-2"));
+}
+
+#[test]
+fn never_evicts_protected_entries() {
+    let dir = make_test_dir("evict-protected");
+    let now = SystemTime::now();
+    let mut manifest = make_manifest(&dir, &[(10, 40)], now);
+    let policy = EvictionPolicy {
+        max_size_mb: Some(0),
+        max_age_days: Some(30),
+    };
+    let protected = vec![dir.join("song-0")].into_iter().collect();
+    let evicted = manifest.evict(&policy, now, &protected);
+    assert!(evicted.is_empty());
+    assert_eq!(manifest.get_entries().len(), 1);
+}
+
+#[test]
+fn estimates_remaining_preload_time() {
+    let elapsed = Duration::from_secs(60);
+    let progress = PreloadProgress {
+        completed: 0,
+        total: 80,
+    };
+    assert_eq!(progress.estimate_remaining_time(elapsed), None);
+    let progress = PreloadProgress {
+        completed: 20,
+        total: 80,
+    };
+    assert_eq!(
+        progress.estimate_remaining_time(elapsed),
+        Some(Duration::from_secs(180))
+    );
+    let progress = PreloadProgress {
+        completed: 80,
+        total: 80,
+    };
+    assert_eq!(
+        progress.estimate_remaining_time(elapsed),
+        Some(Duration::from_secs(0))
+    );
+}
+
+#[test]
+fn parses_loudness_measurement() {
+    let output = r#"[Parsed_loudnorm_0 @ 0x55d5c1e0] 
+{
+	"input_i" : "-23.54",
+	"input_tp" : "-4.20",
+	"input_lra" : "5.30",
+	"input_thresh" : "-33.91",
+	"output_i" : "-16.02",
+	"output_tp" : "-1.50",
+	"output_lra" : "4.60",
+	"output_thresh" : "-26.38",
+	"normalization_type" : "dynamic",
+	"target_offset" : "0.02"
+}
+"#;
+    assert_eq!(normalize::parse_loudness(output).unwrap(), -23.54);
+    assert!(normalize::parse_loudness(&output.replace("-23.54", "-inf")).is_err());
+    assert!(normalize::parse_loudness("ffmpeg version 4.4").is_err());
+}
+
+#[test]
+fn offline_mode_does_not_download_missing_songs() {
+    let directory = make_test_dir("offline");
+    let cache_dir = make_test_dir("offline-cache");
+    fs::write(directory.join("level-99-offline-test"), b"audio").unwrap();
+    let downloader = Fixtures::new(directory);
+    let normalization = NormalizationConfig {
+        enabled: false,
+        ..Default::default()
+    };
+    let song = Song {
+        url: "https://www.youtube.com/watch?v=level-99-offline-test".to_owned(),
+        start: None,
+        end: None,
+    };
+    let error = preload_song(
+        &downloader,
+        &cache_dir,
+        &normalization,
+        true,
+        &mut HashMap::new(),
+        &song,
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("offline mode"));
+    assert!(!url_to_cache_path(&cache_dir, &song.url).unwrap().exists());
+}
+
+#[test]
+fn download_failures_are_shared_by_url() {
+    let directory = make_test_dir("download-failures");
+    let cache_dir = make_test_dir("download-failures-cache");
+    let downloader = Fixtures::new(directory.clone());
+    let normalization = NormalizationConfig {
+        enabled: false,
+        ..Default::default()
+    };
+    let url = "https://www.youtube.com/watch?v=level-99-download-failure-test";
+    let mut download_failures = HashMap::new();
+    let first = Song {
+        url: url.to_owned(),
+        start: Some(Duration::from_secs(10)),
+        end: Some(Duration::from_secs(20)),
+    };
+    assert!(preload_song(
+        &downloader,
+        &cache_dir,
+        &normalization,
+        false,
+        &mut download_failures,
+        &first
+    )
+    .is_err());
+    assert!(download_failures.contains_key(url));
+
+    // The fixture appearing afterwards shows that the second excerpt reuses the failure
+    fs::write(directory.join("level-99-download-failure-test"), b"audio").unwrap();
+    let second = Song {
+        url: url.to_owned(),
+        start: Some(Duration::from_secs(30)),
+        end: None,
+    };
+    assert!(preload_song(
+        &downloader,
+        &cache_dir,
+        &normalization,
+        false,
+        &mut download_failures,
+        &second
+    )
+    .is_err());
+    assert!(!url_to_cache_path(&cache_dir, url).unwrap().exists());
+}
+
+#[test]
+fn youtube_urls_without_scheme_are_not_local() {
+    let base_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    assert!(is_local("file:///home/me/song.mp3"));
+    assert!(is_local_path("assets/question.wav", base_dir));
+    assert!(!is_local_path("assets/missing.wav", base_dir));
+    assert!(!is_local_path("youtube.com/watch?v=abc123", base_dir));
+    assert!(!is_local_path("www.youtube.com/watch?v=abc123", base_dir));
+    assert!(!is_local_path("youtu.be/abc123", base_dir));
+}
+
+#[test]
+fn parses_all_youtube_url_shapes() {
+    let urls = [
+        "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+        "https://youtube.com/watch?feature=share&v=dQw4w9WgXcQ",
+        "http://m.youtube.com/watch?v=dQw4w9WgXcQ",
+        "https://music.youtube.com/watch?v=dQw4w9WgXcQ&list=RDAMVM",
+        "https://youtu.be/dQw4w9WgXcQ",
+        "https://www.youtube.com/shorts/dQw4w9WgXcQ",
+        "https://www.youtube.com/embed/dQw4w9WgXcQ?rel=0",
+        "https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ",
+        "https://www.youtube.com/live/dQw4w9WgXcQ",
+        "www.youtube.com/watch?v=dQw4w9WgXcQ",
+    ];
+    for url in &urls {
+        let parsed = YoutubeUrl::parse(url).unwrap();
+        assert_eq!(parsed.video_id, "dQw4w9WgXcQ", "{}", url);
+        assert_eq!(parsed.start, None, "{}", url);
+    }
+}
+
+#[test]
+fn rejects_urls_without_video_id() {
+    let urls = [
+        "https://example.com/watch?v=dQw4w9WgXcQ",
+        "https://www.youtube.com/",
+        "https://www.youtube.com/watch?list=RDAMVM",
+        "https://www.youtube.com/channel/UC38IQsAvIsxxjztdMZQtwHA",
+        "https://youtu.be/",
+        "https://www.youtube.com/watch?v=not%20an%20id",
+    ];
+    for url in &urls {
+        assert!(YoutubeUrl::parse(url).is_err(), "{}", url);
+    }
+}
+
+#[test]
+fn parses_youtube_timestamps() {
+    let cases = [
+        ("https://www.youtube.com/watch?v=abc123&t=90", 90),
+        ("https://www.youtube.com/watch?t=90s&v=abc123", 90),
+        ("https://youtu.be/abc123?t=1m30s", 90),
+        ("https://youtu.be/abc123?t=1h2m3s", 3723),
+        ("https://youtu.be/abc123?t=2m", 120),
+        ("https://www.youtube.com/embed/abc123?start=45", 45),
+        ("https://www.youtube.com/watch?v=abc123#t=1m5s", 65),
+    ];
+    for (url, seconds) in &cases {
+        let parsed = YoutubeUrl::parse(url).unwrap();
+        assert_eq!(parsed.start, Some(Duration::from_secs(*seconds)), "{}", url);
+    }
+    assert!(YoutubeUrl::parse("https://youtu.be/abc123?t=soon").is_err());
+}
+
+#[test]
+fn songs_that_cannot_be_normalized_are_still_playable() {
+    let path = make_test_dir("unnormalized").join("not-audio.wav");
+    fs::write(&path, b"not audio").unwrap();
+    let normalization = NormalizationConfig {
+        enabled: true,
+        ..Default::default()
+    };
+    let song = Song {
+        url: format!("file://{}", path.to_string_lossy()),
+        start: None,
+        end: None,
+    };
+    let state = preload_song(
+        &Fixtures::new(env::temp_dir()),
+        &make_test_dir("unnormalized-cache"),
+        &normalization,
+        false,
+        &mut HashMap::new(),
+        &song,
+    )
+    .unwrap();
+    assert!(matches!(state, SongState::Unnormalized(_)));
+}
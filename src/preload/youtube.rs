@@ -0,0 +1,114 @@
+use anyhow::*;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::time::Duration;
+
+const HOSTS: [&str; 4] = [
+    "youtube.com",
+    "music.youtube.com",
+    "youtube-nocookie.com",
+    "youtu.be",
+];
+
+// Path prefixes under which the video ID is the next path segment
+const ID_PATH_PREFIXES: [&str; 5] = ["shorts", "embed", "v", "live", "e"];
+
+lazy_static! {
+    static ref VIDEO_ID_REGEX: Regex = Regex::new("^[A-Za-z0-9_-]+$").unwrap();
+    static ref TIMESTAMP_REGEX: Regex =
+        Regex::new("^(?:([0-9]+)h)?(?:([0-9]+)m)?(?:([0-9]+)s?)?$").unwrap();
+}
+
+// The parts of a Youtube link that matter to the quiz, regardless of which form of link was used
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct YoutubeUrl {
+    pub video_id: String,
+    pub start: Option<Duration>,
+}
+
+impl YoutubeUrl {
+    pub fn parse(url: &str) -> Result<YoutubeUrl> {
+        let without_scheme = url
+            .trim()
+            .strip_prefix("https://")
+            .or_else(|| url.trim().strip_prefix("http://"))
+            .unwrap_or_else(|| url.trim());
+        let (without_fragment, fragment) = split_once(without_scheme, '#');
+        let (without_query, query) = split_once(without_fragment, '?');
+        let (host, path) = split_once(without_query, '/');
+
+        let host = host.to_lowercase();
+        let host = host
+            .strip_prefix("www.")
+            .or_else(|| host.strip_prefix("m."))
+            .unwrap_or(&host);
+        if !HOSTS.contains(&host) {
+            return Err(anyhow!("Not a Youtube URL: {}", url));
+        }
+
+        let parameters = parse_parameters(query.unwrap_or_default())
+            .chain(parse_parameters(fragment.unwrap_or_default()))
+            .collect::<Vec<_>>();
+        let find_parameter = |name: &str| {
+            parameters
+                .iter()
+                .find(|(key, _value)| *key == name)
+                .map(|(_key, value)| *value)
+        };
+
+        let segments: Vec<&str> = path
+            .unwrap_or_default()
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .collect();
+        let video_id = match segments.as_slice() {
+            [id] if host == "youtu.be" => Some(*id),
+            ["watch"] => find_parameter("v"),
+            [prefix, id, ..] if ID_PATH_PREFIXES.contains(prefix) => Some(*id),
+            _ => None,
+        };
+        let video_id = video_id
+            .filter(|id| VIDEO_ID_REGEX.is_match(id))
+            .context("No video ID in URL")?;
+
+        let start = match find_parameter("t").or_else(|| find_parameter("start")) {
+            Some(timestamp) => Some(parse_timestamp(timestamp)?),
+            None => None,
+        };
+
+        Ok(YoutubeUrl {
+            video_id: video_id.to_owned(),
+            start,
+        })
+    }
+}
+
+fn split_once(input: &str, separator: char) -> (&str, Option<&str>) {
+    match input.find(separator) {
+        Some(index) => (&input[..index], Some(&input[index + 1..])),
+        None => (input, None),
+    }
+}
+
+fn parse_parameters(input: &str) -> impl Iterator<Item = (&str, &str)> {
+    input
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| split_once(p, '='))
+        .map(|(key, value)| (key, value.unwrap_or_default()))
+}
+
+// Accepts timestamps in seconds (`90`, `90s`) as well as `1m30s` or `1h2m3s`
+pub fn parse_timestamp(timestamp: &str) -> Result<Duration> {
+    let captures = TIMESTAMP_REGEX
+        .captures(timestamp)
+        .filter(|_| !timestamp.is_empty())
+        .with_context(|| format!("Invalid timestamp: {}", timestamp))?;
+    let mut seconds = 0;
+    for (index, multiplier) in [(1, 60 * 60), (2, 60), (3, 1)] {
+        if let Some(value) = captures.get(index) {
+            seconds += value.as_str().parse::<u64>()? * multiplier;
+        }
+    }
+    Ok(Duration::from_secs(seconds))
+}
@@ -0,0 +1,283 @@
+use anyhow::*;
+use parking_lot::{Mutex, RwLock};
+use serenity::model::id::UserId;
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::game::quiz::definition::QuizDefinition;
+use crate::game::quiz::settings::SettingsOverrides;
+use crate::game::quiz::snapshot;
+use crate::game::stats::{self, PlayerStats};
+use crate::game::team::{self, sanitize_name, TeamId};
+use crate::game::{Game, GameMode};
+use crate::output::recorder::{self, Recorder};
+use crate::output::terminal::{AudioSink, TerminalGameOutput};
+use crate::output::GameOutput;
+use crate::preload::cache;
+use crate::preload::Song;
+
+const DEFAULT_PLAYER_NAME: &str = "player";
+const LEADERBOARD_SIZE: usize = 10;
+
+const HELP: &str = "Commands:
+  !player name        act as another player (created on first use)
+  !team team-name     create or join a team
+  !mode solo|teams    let every player compete on their own, or in teams
+  !begin quiz.csv     start a quiz, optionally followed by settings like vote=20 options=4
+  !resume             resume the last interrupted quiz
+  !guess something    submit a guess for your team
+  !wager amount       wager points on a challenge question
+  !vote number        vote for the next category
+  !stats [name]       show statistics of a player, yourself by default
+  !leaderboard        rank players across all quizzes
+  !continue           play without the songs that could not be downloaded
+  !skip               advance to the next quiz phase
+  !pause / !unpause   pause or resume the quiz
+  !score team delta   adjust a team's score
+  !history team-name  list the score changes of a team
+  !undo               revert the last score change
+  !accept team-name   count a team's guess for the last question as correct
+  !reject team-name   count a team's guess for the last question as incorrect
+                      (in solo mode, !score, !history, !accept and !reject take a player name)
+  !disband team-name  delete a team
+  !reset scores|teams reset all scores or all teams
+  !cache list|prune   list cached songs or evict old ones
+  !cache warm quiz.csv download the songs of a quiz ahead of time
+  !end                stop the quiz
+  !quit               exit";
+
+struct Players {
+    names: HashMap<String, UserId>,
+    current: UserId,
+}
+
+impl Players {
+    fn new() -> Self {
+        let mut players = Players {
+            names: HashMap::new(),
+            current: UserId(0),
+        };
+        players.switch_to(DEFAULT_PLAYER_NAME);
+        players
+    }
+
+    fn get_name(&self, id: UserId) -> String {
+        self.names
+            .iter()
+            .find(|(_name, player_id)| **player_id == id)
+            .map(|(name, _player_id)| name.clone())
+            .unwrap_or_else(|| format!("player #{}", id.0))
+    }
+
+    fn switch_to(&mut self, name: &str) {
+        let next_id = UserId(self.names.len() as u64 + 1);
+        self.current = *self.names.entry(name.to_owned()).or_insert(next_id);
+    }
+
+    // Solo players are named where Discord commands would mention them
+    fn parse_team_id(&self, mode: GameMode, name: &str) -> Result<TeamId> {
+        match mode {
+            GameMode::Teams => Ok(TeamId::TeamName(sanitize_name(name)?)),
+            GameMode::Solo => self
+                .names
+                .get(name)
+                .map(|id| TeamId::Player(*id))
+                .context("Unknown player"),
+        }
+    }
+}
+
+pub fn run(audio_sink: AudioSink) -> Result<()> {
+    let teams = Arc::new(RwLock::new(Vec::new()));
+    let output = TerminalGameOutput::new(teams.clone(), audio_sink);
+    let snapshot_path = snapshot::get_snapshot_path("terminal").ok();
+    let log_directory = recorder::get_log_directory("terminal").ok();
+    let recorder = Recorder::new(output.clone(), log_directory);
+    let stats_path = stats::get_stats_path("terminal").ok();
+    let game = Arc::new(Mutex::new(Game::new(
+        recorder,
+        teams,
+        snapshot_path,
+        stats_path,
+    )));
+
+    let game_for_ticker = Arc::clone(&game);
+    let _game_ticker = thread::spawn(move || {
+        let mut last_tick_time = Instant::now();
+        loop {
+            let now = Instant::now();
+            let dt = now.duration_since(last_tick_time);
+            last_tick_time = now;
+            game_for_ticker.lock().tick(dt);
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    });
+
+    println!("{}", HELP);
+    let mut players = Players::new();
+    output.set_player_name(players.current, DEFAULT_PLAYER_NAME);
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line == "!quit" {
+            break;
+        }
+        if let Err(e) = execute(line, &game, &output, &mut players) {
+            eprintln!("{:#}", e);
+        }
+    }
+
+    output.stop_audio().ok();
+    Ok(())
+}
+
+// Solo players join on their first guess or wager
+fn join_solo_game(game: &mut Game<Recorder<TerminalGameOutput>>, players: &Players) -> Result<()> {
+    if game.get_mode() == GameMode::Solo {
+        game.add_solo_player(players.current, &players.get_name(players.current))?;
+    }
+    Ok(())
+}
+
+fn execute(
+    line: &str,
+    game: &Mutex<Game<Recorder<TerminalGameOutput>>>,
+    output: &TerminalGameOutput,
+    players: &mut Players,
+) -> Result<()> {
+    let (command, args) = match line.find(char::is_whitespace) {
+        Some(index) => (&line[..index], line[index..].trim()),
+        None => (line, ""),
+    };
+    let player = players.current;
+    let mut game = game.lock();
+    match command {
+        "!help" => println!("{}", HELP),
+        "!player" => {
+            if args.is_empty() {
+                return Err(anyhow!("Player name cannot be blank"));
+            }
+            players.switch_to(args);
+            output.set_player_name(players.current, args);
+            println!("You are now playing as {}", args);
+        }
+        "!team" => game.join_team(player, args)?,
+        "!mode" => game.set_mode(args.parse()?)?,
+        "!begin" => {
+            let (path_string, settings_overrides) = SettingsOverrides::split_from_path(args)?;
+            if path_string.is_empty() {
+                return Err(anyhow!("Filename cannot be blank"));
+            }
+            let path = Path::new(path_string);
+            game.begin(path, settings_overrides)
+                .with_context(|| format!("Could not begin quiz with path {:?}", path))?;
+        }
+        "!resume" => game.resume(|_teams| Ok(HashMap::new()))?,
+        "!guess" => {
+            if !args.is_empty() {
+                join_solo_game(&mut game, players)?;
+                game.guess(player, args)?;
+            }
+        }
+        "!wager" => {
+            let amount = args.parse().context("Could not parse wager amount")?;
+            join_solo_game(&mut game, players)?;
+            game.wager(player, amount)?;
+        }
+        "!vote" => {
+            let option = args.parse().context("Could not parse vote option")?;
+            output.vote(player, option)?;
+        }
+        "!stats" => {
+            let (name, id) = if args.is_empty() {
+                (players.get_name(player), player)
+            } else {
+                let id = *players.names.get(args).context("Unknown player")?;
+                (args.to_owned(), id)
+            };
+            println!(
+                "{}",
+                stats::describe_player(&name, &game.get_player_stats(id))
+            );
+        }
+        "!leaderboard" => {
+            let entries: Vec<(String, PlayerStats)> = game
+                .get_leaderboard(LEADERBOARD_SIZE)
+                .into_iter()
+                .map(|(id, stats)| (players.get_name(id), stats))
+                .collect();
+            println!("{}", stats::describe_leaderboard(&entries));
+        }
+        "!continue" => game.continue_quiz()?,
+        "!skip" => game.skip()?,
+        "!pause" => game.pause(),
+        "!unpause" => game.unpause(),
+        "!end" => game.end()?,
+        "!score" => {
+            let mut args = args.split_whitespace();
+            let team_name = args.next().context("Could not parse team name")?;
+            let score_delta = args
+                .next()
+                .and_then(|d| d.parse().ok())
+                .context("Could not parse score delta")?;
+            let team_id = players.parse_team_id(game.get_mode(), team_name)?;
+            game.adjust_score(team_id, score_delta)?;
+        }
+        "!history" => {
+            let team_id = players.parse_team_id(game.get_mode(), args)?;
+            let history = game.get_score_history(&team_id)?;
+            let name = match &team_id {
+                TeamId::TeamName(name) => name.clone(),
+                TeamId::Player(_) => args.to_owned(),
+            };
+            println!("{}", team::describe_score_history(&name, &history));
+        }
+        "!undo" => game.undo()?,
+        "!accept" | "!reject" => {
+            let team_id = players.parse_team_id(game.get_mode(), args)?;
+            game.rejudge_guess(team_id, command == "!accept")?;
+        }
+        "!disband" => game.disband_team(args)?,
+        "!cache" => {
+            let (subcommand, path_string) = match args.find(char::is_whitespace) {
+                Some(index) => (&args[..index], args[index..].trim()),
+                None => (args, ""),
+            };
+            match subcommand {
+                "list" => println!("{}", cache::describe(&cache::list(), usize::MAX)),
+                "prune" => println!("{}", cache::describe_pruned(&cache::prune()?)),
+                "warm" => {
+                    let path = Path::new(path_string);
+                    let definition = QuizDefinition::open(path)
+                        .with_context(|| format!("Could not open quiz with path {:?}", path))?;
+                    let songs: Vec<Song> = definition
+                        .get_questions()
+                        .iter()
+                        .map(|q| q.get_song())
+                        .collect();
+                    println!("Preloading {} song(s) in the background", songs.len());
+                    cache::warm(songs, |description| println!("{}", description))?;
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "Use `!cache list`, `!cache prune` or `!cache warm quiz.csv`"
+                    ))
+                }
+            }
+        }
+        "!reset" => match args {
+            "scores" => game.reset_scores(),
+            "teams" => game.reset_teams(),
+            _ => return Err(anyhow!("Use `!reset scores` or `!reset teams`")),
+        },
+        _ => return Err(anyhow!("Unknown command, use `!help` to list commands")),
+    }
+    Ok(())
+}